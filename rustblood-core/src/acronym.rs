@@ -0,0 +1,34 @@
+/// Builds an acronym for a handle from the first letter of each of its words.
+///
+/// Words are split on anything that isn't a letter or digit and on camelCase
+/// boundaries, so `ectoBiologist`, `ecto biologist` and `ecto_biologist` all give
+/// `EB`, while `KARKAT` gives `K` and `HTMLParser` gives `HP`. Case is decided
+/// with Unicode rules. With a `max_length`, only that many words are used.
+pub fn derive_acronym(handle: &str, max_length: Option<usize>) -> String {
+    let chars: Vec<char> = handle.chars().collect();
+    let mut acronym = String::new();
+    let mut words = 0;
+
+    for (i, &c) in chars.iter().enumerate() {
+        if max_length.is_some_and(|max| words >= max) {
+            break;
+        }
+        if !c.is_alphanumeric() {
+            continue;
+        }
+
+        let starts_word = match i.checked_sub(1).map(|p| chars[p]) {
+            None => true,
+            Some(prev) if !prev.is_alphanumeric() => true,
+            // A capital ends a run of lowercase letters or digits, or starts the
+            // last word after a run of capitals, like the P in HTMLParser.
+            Some(prev) => c.is_uppercase() && (!prev.is_uppercase() || chars.get(i + 1).is_some_and(|n| n.is_lowercase())),
+        };
+        if starts_word {
+            acronym.extend(c.to_uppercase());
+            words += 1;
+        }
+    }
+
+    acronym
+}
@@ -0,0 +1,179 @@
+use crate::memo::{Memo, Timeline};
+use crate::quirk::Character;
+use crate::target::Target;
+
+/// Built-in action commands and the arguments they take, as listed by `help`.
+/// `reroll` acts on a message that was already sent, so it's run by the bot rather
+/// than by `Character::command_in`, which leaves it to custom commands.
+pub const BUILTIN_COMMANDS: &[(&str, &str)] = &[
+    ("join", ""),
+    ("leave", ""),
+    ("idle", ""),
+    ("unidle", ""),
+    ("online", ""),
+    ("offline", ""),
+    ("block", "userName"),
+    ("unblock", "userName"),
+    ("kick", "userName"),
+    ("ban", "userName"),
+    ("unban", "userName"),
+    ("upload", "filename"),
+    ("troll", "userName"),
+    ("pester", "userName"),
+    ("cease", "userName"),
+    ("timeline", "-H:MM"),
+    ("help", ""),
+    ("profile", ""),
+    ("reroll", ""),
+];
+
+/// The sentence shown in quirk form on profile cards.
+pub const SAMPLE_SENTENCE: &str = "The quick brown fox jumped over the lazy dog.";
+
+pub fn online(c: &Character) -> String {
+    format!("```\n-- {} [{}] is now online! --\n```", c.handle, c.acronym)
+}
+
+pub fn offline(c: &Character) -> String {
+    format!("```\n-- {} [{}] is now offline! --\n```", c.handle, c.acronym)
+}
+
+pub fn idle(c: &Character) -> String {
+    format!("```\n-- {} [{}] is now idle! --\n```", c.handle, c.acronym)
+}
+
+pub fn unidle(c: &Character) -> String {
+    format!("```\n-- {} [{}] is no longer idle! --\n```", c.handle, c.acronym)
+}
+
+pub fn join(c: &Character) -> String {
+    format!("```\n-- {} [{}] has joined the memo! --\n```", c.handle, c.acronym)
+}
+
+pub fn leave(c: &Character) -> String {
+    format!("```\n-- {} [{}] has left the memo! --\n```", c.handle, c.acronym)
+}
+
+pub fn block(c: &Character, user: &Target) -> String {
+    format!("```\n-- {} [{}] has blocked {} [{}]! --\n```", c.handle, c.acronym, user.handle, user.acronym)
+}
+
+pub fn unblock(c: &Character, user: &Target) -> String {
+    format!("```\n-- {} [{}] has unblocked {} [{}]! --\n```", c.handle, c.acronym, user.handle, user.acronym)
+}
+
+pub fn kick(c: &Character, user: &Target) -> String {
+    format!("```\n-- {} [{}] has kicked {} [{}] from the memo! --\n```", c.handle, c.acronym, user.handle, user.acronym)
+}
+
+pub fn ban(c: &Character, user: &Target) -> String {
+    format!("```\n-- {} [{}] has banned {} [{}] from the memo! --\n```", c.handle, c.acronym, user.handle, user.acronym)
+}
+
+pub fn unban(c: &Character, user: &Target) -> String {
+    format!("```\n-- {} [{}] has unbanned {} [{}] from the memo! --\n```", c.handle, c.acronym, user.handle, user.acronym)
+}
+
+pub fn upload(c: &Character, file: &str) -> String {
+    format!("```\n-- {} [{}] has uploaded \"{}\" --\n```", c.handle, c.acronym, file)
+}
+
+pub fn troll(c: &Character, user: &Target) -> String {
+    format!("```\n-- {} [{}] has begun trolling {} [{}]! --\n```", c.handle, c.acronym, user.handle, user.acronym)
+}
+
+pub fn pester(c: &Character, user: &Target, time: Option<&str>) -> String {
+    match time {
+        Some(time) => format!("```\n-- {} [{}] began pestering {} [{}] at {} --\n```", c.handle, c.acronym, user.handle, user.acronym, time),
+        None => format!("```\n-- {} [{}] began pestering {} [{}] --\n```", c.handle, c.acronym, user.handle, user.acronym),
+    }
+}
+
+pub fn cease(c: &Character, user: &Target, time: Option<&str>) -> String {
+    match time {
+        Some(time) => format!("```\n-- {} [{}] ceased pestering {} [{}] at {} --\n```", c.handle, c.acronym, user.handle, user.acronym, time),
+        None => format!("```\n-- {} [{}] ceased pestering {} [{}] --\n```", c.handle, c.acronym, user.handle, user.acronym),
+    }
+}
+
+pub fn responded(c: &Character, t: &Timeline) -> String {
+    format!("```\n{} {} ({}{}) {} responded to memo.\n```", t.label(), c.handle, t.letter(), c.acronym, t.relative())
+}
+
+pub fn ceased_responding(c: &Character, t: &Timeline) -> String {
+    format!("```\n{} {} ({}{}) {} ceased responding to memo.\n```", t.label(), c.handle, t.letter(), c.acronym, t.relative())
+}
+
+pub fn banned(c: &Character) -> String {
+    format!("```\n-- {} [{}] is banned from the memo! --\n```", c.handle, c.acronym)
+}
+
+pub fn who(memo: &Memo) -> String {
+    if memo.participants.is_empty() {
+        return "```\n-- Nobody is responding to the memo --\n```".to_string();
+    }
+
+    let mut lines = vec!["-- Responding to the memo --".to_string()];
+    for p in &memo.participants {
+        match memo.timeline(&p.name) {
+            Some(t) => lines.push(format!("{} {} ({}{}) {}", t.label(), p.handle, t.letter(), p.acronym, t.relative())),
+            None => lines.push(format!("{} [{}]", p.handle, p.acronym)),
+        }
+    }
+    format!("```\n{}\n```", lines.join("\n"))
+}
+
+pub fn help(c: &Character) -> String {
+    let usage = |name: &str, args: &str| {
+        if args.is_empty() {
+            format!("{}! {}", c.name, name)
+        } else {
+            format!("{}! {} {}", c.name, name, args)
+        }
+    };
+
+    let mut lines = vec![format!("-- {} [{}] can use the following commands --", c.handle, c.acronym)];
+    for (name, args) in BUILTIN_COMMANDS {
+        lines.push(usage(name, args));
+    }
+    for (name, cmd) in &c.commands {
+        if !BUILTIN_COMMANDS.iter().any(|(builtin, _)| builtin == name) {
+            lines.push(usage(name, &cmd.args.join(" ")));
+        }
+    }
+    format!("```\n{}\n```", lines.join("\n"))
+}
+
+pub fn list(characters: &[Character]) -> String {
+    if characters.is_empty() {
+        return "```\n-- No characters found in the quirks folder --\n```".to_string();
+    }
+
+    let mut lines = vec!["-- Available characters --".to_string()];
+    for c in characters {
+        lines.push(format!("{}: {} [{}]", c.name, c.handle, c.acronym));
+    }
+    format!("```\n{}\n```", lines.join("\n"))
+}
+
+pub fn profile(c: &Character) -> String {
+    let mut lines = vec![format!("-- {} [{}] --", c.handle, c.acronym)];
+    let fields = [
+        ("Name", &c.full_name),
+        ("Pronouns", &c.pronouns),
+        ("Blood", &c.blood_color),
+        ("Avatar", &c.avatar),
+    ];
+    for (label, value) in fields {
+        if let Some(value) = value {
+            lines.push(format!("{}: {}", label, value));
+        }
+    }
+    if let Some(description) = &c.description {
+        lines.push(String::new());
+        lines.push(description.to_string());
+    }
+    lines.push(String::new());
+    lines.push(c.quirked(SAMPLE_SENTENCE));
+    format!("```\n{}\n```", lines.join("\n"))
+}
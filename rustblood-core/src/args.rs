@@ -0,0 +1,87 @@
+/// Arguments given to an action command, tokenized in a shell-like manner.
+///
+/// Tokens are separated by any amount of whitespace. Double quotes group words
+/// into a single token, and `\"` or `\\` insert a literal quote or backslash.
+/// Any other backslash is kept as-is so that things like Windows paths survive.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Args {
+    raw: String,
+    tokens: Vec<(usize, String)>,
+}
+
+impl Args {
+    pub fn parse(s: &str) -> Args {
+        let mut tokens: Vec<(usize, String)> = Vec::new();
+        let mut current: Option<(usize, String)> = None;
+        let mut in_quotes = false;
+        let mut chars = s.char_indices().peekable();
+
+        while let Some((i, ch)) = chars.next() {
+            match ch {
+                '\\' if matches!(chars.peek(), Some((_, '"')) | Some((_, '\\'))) => {
+                    let (_, escaped) = chars.next().unwrap();
+                    current.get_or_insert_with(|| (i, String::new())).1.push(escaped);
+                },
+                '"' => {
+                    // An empty pair of quotes is still an (empty) argument.
+                    current.get_or_insert_with(|| (i, String::new()));
+                    in_quotes = !in_quotes;
+                },
+                c if c.is_whitespace() && !in_quotes => {
+                    if let Some(token) = current.take() {
+                        tokens.push(token);
+                    }
+                },
+                c => {
+                    current.get_or_insert_with(|| (i, String::new())).1.push(c);
+                }
+            }
+        }
+        if let Some(token) = current.take() {
+            tokens.push(token);
+        }
+
+        Args { raw: s.to_owned(), tokens }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Option<&str> {
+        self.tokens.get(i).map(|t| t.1.as_str())
+    }
+
+    /// Everything from the `i`th argument to the end of the line.
+    ///
+    /// If only one argument remains its tokenized value is returned, so that
+    /// `kick "two words"` and `kick two words` both yield `two words`.
+    /// Otherwise the remainder is returned exactly as typed.
+    pub fn rest(&self, i: usize) -> Option<String> {
+        if i + 1 == self.tokens.len() {
+            return self.get(i).map(|s| s.to_string());
+        }
+        self.tokens.get(i).map(|t| self.raw[t.0..].trim_end().to_string())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.tokens.iter().map(|t| t.1.as_str())
+    }
+
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+}
+
+/// Splits the text following a `NAME! ` prefix into the command name and its arguments.
+pub fn parse_command(s: &str) -> (String, Args) {
+    let s = s.trim_start();
+    match s.find(char::is_whitespace) {
+        Some(i) => (s[..i].to_string(), Args::parse(s[i..].trim_start())),
+        None => (s.to_string(), Args::default()),
+    }
+}
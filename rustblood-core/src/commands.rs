@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+
+use crate::args::Args;
+use crate::memo::Memo;
+use crate::quirk::{Character, Context, quirks_dir, strip_jsonc_comments};
+use crate::settings::Settings;
+use crate::target::Target;
+
+/// Name of the file in the quirks directory holding commands shared by every character.
+/// The leading underscore keeps it from ever being loaded as a character.
+pub const SHARED_COMMANDS_FILE: &str = "_commands.json";
+
+/// An action command declared in a character file or the shared commands file.
+///
+/// `template` may reference `{handle}` and `{acronym}` of the character running the
+/// command, as well as each declared argument by name. `{name.acronym}` gives the
+/// acronym of an argument, e.g. `{user.acronym}`. The last argument takes the rest
+/// of the line.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CustomCommand {
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub template: String,
+}
+
+impl CustomCommand {
+    /// Renders the command with the default settings, looking up other characters
+    /// in the default quirks directory.
+    pub fn render(&self, c: &Character, args: &Args) -> Option<String> {
+        let (dir, settings, mut memo) = (quirks_dir(), Settings::default(), Memo::default());
+        self.render_in(c, args, &Context::new(&dir, &settings, &mut memo))
+    }
+
+    /// Renders the command, returning `None` if an argument is missing.
+    /// Arguments naming a known character are replaced by that character's handle.
+    pub fn render_in(&self, c: &Character, args: &Args, ctx: &Context) -> Option<String> {
+        let mut string = self.template.replace("{handle}", &c.handle).replace("{acronym}", &c.acronym);
+        for (i, name) in self.args.iter().enumerate() {
+            let value = if i + 1 == self.args.len() {
+                args.rest(i)?
+            } else {
+                args.get(i)?.to_string()
+            };
+            let (acronym, handle) = (format!("{{{}.acronym}}", name), format!("{{{}}}", name));
+            if !string.contains(&acronym) && !string.contains(&handle) {
+                continue;
+            }
+            // Only arguments the template uses are looked up among the characters.
+            let target = Target::resolve_among(ctx.characters(), &value, ctx.settings);
+            string = string.replace(&acronym, &target.acronym).replace(&handle, &target.handle);
+        }
+
+        Some(format!("```\n{}\n```", string))
+    }
+}
+
+/// Loads the shared commands file from the given quirks directory, if there is one.
+/// A file which fails to parse is reported and treated as holding no commands.
+pub fn load_shared_commands(dir: &Path) -> BTreeMap<String, CustomCommand> {
+    let data = match fs::read_to_string(dir.join(SHARED_COMMANDS_FILE)) {
+        Ok(data) => data,
+        Err(_) => return BTreeMap::new(),
+    };
+    match serde_json::from_str(&strip_jsonc_comments(&data, true)) {
+        Ok(commands) => commands,
+        Err(err) => {
+            eprintln!("Ignoring {}: {}", SHARED_COMMANDS_FILE, err);
+            BTreeMap::new()
+        },
+    }
+}
@@ -0,0 +1,26 @@
+//! The quirk engine behind rustblood: loading characters, parsing messages and
+//! rendering dialogue and action commands in the style of a Pesterchum memo.
+
+pub mod acronym;
+pub mod actions;
+pub mod args;
+pub mod commands;
+pub mod memo;
+pub mod message;
+pub mod presence;
+pub mod quirk;
+pub mod settings;
+pub mod target;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+
+mod tests;
+
+pub use args::Args;
+pub use commands::CustomCommand;
+pub use memo::{Memo, Participant, Timeline};
+pub use message::{Line, ParsedMessage};
+pub use presence::{Presence, PresenceChange};
+pub use quirk::{Character, Characters, Context, quirks_dir};
+pub use settings::{Delivery, ReactionAction, Settings};
+pub use target::Target;
@@ -0,0 +1,142 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::quirk::Character;
+
+/// A time offset from the present, in minutes, which a character is speaking from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Timeline {
+    pub minutes: i32,
+}
+
+impl Timeline {
+    /// Parses offsets such as `-2:00`, `+1:30`, `-2` (hours) or `now`.
+    /// Offsets without a sign are in the future.
+    pub fn parse(s: &str) -> Option<Timeline> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("now") || s.eq_ignore_ascii_case("current") {
+            return Some(Timeline::default());
+        }
+
+        let (sign, s) = match s.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (hours, minutes) = match s.split_once(':') {
+            Some((h, m)) if m.len() == 2 => (h.parse::<i32>().ok()?, m.parse::<i32>().ok()?),
+            Some(_) => return None,
+            None => (s.parse::<i32>().ok()?, 0),
+        };
+        if hours < 0 || !(0..60).contains(&minutes) {
+            return None;
+        }
+
+        Some(Timeline { minutes: sign * (hours.checked_mul(60)?.checked_add(minutes)?) })
+    }
+
+    /// `CURRENT`, `PAST` or `FUTURE`.
+    pub fn label(&self) -> &'static str {
+        match self.minutes {
+            0 => "CURRENT",
+            m if m < 0 => "PAST",
+            _ => "FUTURE",
+        }
+    }
+
+    /// The letter prefixed to a character's acronym when speaking from this timeline.
+    pub fn letter(&self) -> char {
+        self.label().chars().next().unwrap()
+    }
+
+    /// The offset in words, e.g. `2 HOURS AGO` or `1:30 HOURS FROM NOW`.
+    pub fn relative(&self) -> String {
+        let total = self.minutes.abs();
+        let (hours, minutes) = (total / 60, total % 60);
+        let amount = match (hours, minutes) {
+            (0, 0) => return "RIGHT NOW".to_string(),
+            (0, 1) => "1 MINUTE".to_string(),
+            (0, m) => format!("{} MINUTES", m),
+            (1, 0) => "1 HOUR".to_string(),
+            (h, 0) => format!("{} HOURS", h),
+            (h, m) => format!("{}:{:02} HOURS", h, m),
+        };
+        if self.minutes < 0 {
+            format!("{} AGO", amount)
+        } else {
+            format!("{} FROM NOW", amount)
+        }
+    }
+}
+
+impl fmt::Display for Timeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.minutes < 0 { "-" } else { "+" };
+        write!(f, "{}{}:{:02}", sign, self.minutes.abs() / 60, self.minutes.abs() % 60)
+    }
+}
+
+/// A character currently responding to a memo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Participant {
+    pub name: String,
+    pub handle: String,
+    pub acronym: String,
+}
+
+/// State of a single memo (usually a Discord channel) which lasts between messages.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Memo {
+    /// Timelines set with the `timeline` command, keyed by character name.
+    pub timelines: BTreeMap<String, Timeline>,
+    /// Characters in the memo, in the order they joined.
+    pub participants: Vec<Participant>,
+    /// Handles banned from the memo.
+    pub banned: BTreeSet<String>,
+}
+
+impl Memo {
+    pub fn timeline(&self, name: &str) -> Option<Timeline> {
+        self.timelines.get(name).copied()
+    }
+
+    pub fn is_banned(&self, handle: &str) -> bool {
+        self.banned.contains(handle)
+    }
+
+    pub fn is_participant(&self, handle: &str) -> bool {
+        self.participants.iter().any(|p| p.handle == handle)
+    }
+
+    /// Adds a character to the memo. Returns `false`, leaving the memo
+    /// untouched, if the character is banned.
+    pub fn join(&mut self, c: &Character) -> bool {
+        if self.is_banned(&c.handle) {
+            return false;
+        }
+        if !self.is_participant(&c.handle) {
+            self.participants.push(Participant {
+                name: c.name.clone(),
+                handle: c.handle.clone(),
+                acronym: c.acronym.clone(),
+            });
+        }
+        true
+    }
+
+    /// Removes whoever has the given handle from the memo, along with their timeline.
+    pub fn leave(&mut self, handle: &str) -> Option<Participant> {
+        let i = self.participants.iter().position(|p| p.handle == handle)?;
+        let p = self.participants.remove(i);
+        self.timelines.remove(&p.name);
+        Some(p)
+    }
+
+    pub fn ban(&mut self, handle: &str) {
+        self.leave(handle);
+        self.banned.insert(handle.to_string());
+    }
+
+    pub fn unban(&mut self, handle: &str) {
+        self.banned.remove(handle);
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+
+use regex::Regex;
+
+use crate::args::{Args, parse_command};
+
+/// A single line of a message, classified by how it should be rendered.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Line {
+    /// `NAME: text`, spoken by the character loaded from `NAME`.
+    Dialogue { character: String, text: String },
+    /// `NAME! command args`, an action command run as the character loaded from `NAME`.
+    /// `raw` is the whole line as typed.
+    Command { character: String, name: String, args: Args, raw: String },
+    /// `!command args`, a command which does not belong to any character, such as `!list`.
+    Global { name: String, args: Args, raw: String },
+    /// Any line not addressed to a character.
+    Plain(String),
+    /// A line inside a ``` code block, including the fences themselves. Never quirked.
+    CodeFence(String),
+    /// A line which would have been dialogue or a command, but was prefixed with a
+    /// backslash to send it as-is. Holds the line without the backslash.
+    Escaped(String),
+}
+
+impl Line {
+    /// The text of this line as it was originally typed.
+    pub fn raw(&self) -> String {
+        match self {
+            Line::Dialogue { character, text } => format!("{}: {}", character, text),
+            Line::Command { raw, .. } | Line::Global { raw, .. } => raw.to_string(),
+            Line::Plain(s) | Line::CodeFence(s) => s.to_string(),
+            Line::Escaped(s) => format!("\\{}", s),
+        }
+    }
+
+    /// The character this line is addressed to, if any.
+    pub fn character(&self) -> Option<&str> {
+        match self {
+            Line::Dialogue { character, .. } | Line::Command { character, .. } => Some(character),
+            _ => None,
+        }
+    }
+}
+
+/// A message split into typed lines, ready to be inspected, transformed or rendered.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ParsedMessage {
+    pub lines: Vec<Line>,
+}
+
+impl ParsedMessage {
+    pub fn parse(s: &str) -> ParsedMessage {
+        let regex = Regex::new(r"^(?P<name>[A-Za-z]{1,}): ").unwrap();
+        let regex_cmd = Regex::new(r"^(?P<name>[A-Za-z]{1,})! ").unwrap();
+        let regex_global = Regex::new(r"^![A-Za-z]{1,}(\s|$)").unwrap();
+
+        let mut message = ParsedMessage::default();
+        let mut in_code_block = false;
+        for line in s.split('\n') {
+            if in_code_block || line.starts_with("```") {
+                // A fence only toggles the block if it isn't closed on the same line.
+                if line.matches("```").count() % 2 == 1 {
+                    in_code_block = !in_code_block;
+                }
+                message.lines.push(Line::CodeFence(line.to_string()));
+                continue;
+            }
+
+            if let Some(escaped) = line.strip_prefix('\\') {
+                if regex.is_match(escaped) || regex_cmd.is_match(escaped) || regex_global.is_match(escaped) {
+                    message.lines.push(Line::Escaped(escaped.to_string()));
+                    continue;
+                }
+            }
+
+            if let Some(caps) = regex.captures(line) {
+                message.lines.push(Line::Dialogue {
+                    character: caps["name"].to_string(),
+                    text: line[caps[0].len()..].to_string(),
+                });
+            } else if let Some(caps) = regex_cmd.captures(line) {
+                let (name, args) = parse_command(&line[caps[0].len()..]);
+                message.lines.push(Line::Command {
+                    character: caps["name"].to_string(),
+                    name,
+                    args,
+                    raw: line.to_string(),
+                });
+            } else if regex_global.is_match(line) {
+                let (name, args) = parse_command(&line[1..]);
+                message.lines.push(Line::Global { name, args, raw: line.to_string() });
+            } else {
+                message.lines.push(Line::Plain(line.to_string()));
+            }
+        }
+
+        message
+    }
+
+    /// Names of every character referenced by this message, in order of first appearance.
+    pub fn characters(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for name in self.lines.iter().filter_map(|l| l.character()) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+}
+
+impl fmt::Display for ParsedMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<String> = self.lines.iter().map(|l| l.raw()).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
@@ -0,0 +1,27 @@
+/// The account status a presence command asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Presence {
+    Online,
+    Idle,
+    Offline,
+}
+
+impl Presence {
+    /// The status set by the `online`, `offline`, `idle` and `unidle` commands.
+    pub fn from_command(name: &str) -> Option<Presence> {
+        match name {
+            "online" | "unidle" => Some(Presence::Online),
+            "idle" => Some(Presence::Idle),
+            "offline" => Some(Presence::Offline),
+            _ => None,
+        }
+    }
+}
+
+/// A status change to mirror on the chat account, along with the character's
+/// custom status text if it has one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresenceChange {
+    pub presence: Presence,
+    pub status: Option<String>,
+}
@@ -0,0 +1,651 @@
+use std::collections::BTreeMap;
+use std::cell::OnceCell;
+use std::env::current_dir;
+use std::path::{Path, PathBuf};
+use relative_path::RelativePath;
+use std::fs;
+use rand::seq::SliceRandom;
+use regex::Regex;
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::acronym::derive_acronym;
+use crate::actions::*;
+use crate::args::Args;
+use crate::commands::{CustomCommand, load_shared_commands};
+use crate::memo::{Memo, Timeline};
+use crate::message::{Line, ParsedMessage};
+use crate::presence::{Presence, PresenceChange};
+use crate::settings::{Settings, load_settings};
+use crate::target::Target;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Character {
+    /// The file name this character was loaded from, used to address it in messages.
+    #[serde(skip)]
+    pub name: String,
+    pub handle: String,
+    /// Made from the handle when the file leaves it out.
+    #[serde(default)]
+    pub acronym: String,
+    /// Other names this character can be referred to by in command arguments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pronouns: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blood_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+    /// Custom status text shown while this character is online, if presence sync is on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    pub quirks: Vec<BTreeMap<String, Value>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub commands: BTreeMap<String, CustomCommand>,
+}
+impl Character {
+    pub fn quirked(&self, s: &str) -> String {
+        return quirked(s, self);
+    }
+
+    pub fn online(&self) -> String {
+        return online(self);
+    }
+
+    pub fn offline(&self) -> String {
+        return offline(self);
+    }
+
+    pub fn idle(&self) -> String {
+        return idle(self);
+    }
+
+    pub fn unidle(&self) -> String {
+        return unidle(self);
+    }
+
+    pub fn join(&self) -> String {
+        return join(self);
+    }
+
+    pub fn leave(&self) -> String {
+        return leave(self);
+    }
+
+    pub fn block(&self, user: &Target) -> String {
+        return block(self, user);
+    }
+
+    pub fn unblock(&self, user: &Target) -> String {
+        return unblock(self, user);
+    }
+
+    pub fn upload(&self, file: &str) -> String {
+        return upload(self, file);
+    }
+
+    pub fn kick(&self, user: &Target) -> String {
+        return kick(self, user);
+    }
+
+    pub fn ban(&self, user: &Target) -> String {
+        return ban(self, user);
+    }
+
+    pub fn unban(&self, user: &Target) -> String {
+        return unban(self, user);
+    }
+
+    pub fn troll(&self, user: &Target) -> String {
+        return troll(self, user);
+    }
+
+    pub fn pester(&self, user: &Target, time: Option<&str>) -> String {
+        return pester(self, user, time);
+    }
+
+    pub fn cease(&self, user: &Target, time: Option<&str>) -> String {
+        return cease(self, user, time);
+    }
+
+    /// Whether `name` is a built-in command or one of this character's own.
+    pub fn has_command(&self, name: &str) -> bool {
+        BUILTIN_COMMANDS.iter().any(|(builtin, _)| *builtin == name) || self.commands.contains_key(name)
+    }
+
+    /// Runs the action command `name` with the default settings and an empty memo.
+    pub fn command(&self, name: &str, args: &Args) -> Option<String> {
+        let (dir, settings, mut memo) = (quirks_dir(), Settings::default(), Memo::default());
+        self.command_in(name, args, &mut Context::new(&dir, &settings, &mut memo))
+    }
+
+    /// Runs the action command `name`, returning `None` if the command does not
+    /// exist or is missing a required argument. Built-in commands take priority
+    /// over custom commands of the same name.
+    pub fn command_in(&self, name: &str, args: &Args, ctx: &mut Context) -> Option<String> {
+        let target = |i: usize| args.rest(i).map(|user| Target::resolve_among(ctx.characters(), &user, ctx.settings));
+        match name {
+            "offline" => Some(self.offline()),
+            "online" => Some(self.online()),
+            "idle" => Some(self.idle()),
+            "unidle" => Some(self.unidle()),
+            "join" => {
+                if !ctx.memo.join(self) {
+                    return Some(banned(self));
+                }
+                match ctx.memo.timeline(&self.name) {
+                    Some(t) => Some(responded(self, &t)),
+                    None => Some(self.join()),
+                }
+            },
+            "leave" => {
+                let t = ctx.memo.timeline(&self.name);
+                ctx.memo.leave(&self.handle);
+                ctx.memo.timelines.remove(&self.name);
+                match t {
+                    Some(t) => Some(ceased_responding(self, &t)),
+                    None => Some(self.leave()),
+                }
+            },
+            "timeline" => {
+                let t = Timeline::parse(&args.rest(0)?)?;
+                if !ctx.memo.join(self) {
+                    return Some(banned(self));
+                }
+                ctx.memo.timelines.insert(self.name.clone(), t);
+                Some(responded(self, &t))
+            },
+            "block" => target(0).map(|user| self.block(&user)),
+            "unblock" => target(0).map(|user| self.unblock(&user)),
+            "ban" => target(0).map(|user| {
+                ctx.memo.ban(&user.handle);
+                self.ban(&user)
+            }),
+            "unban" => target(0).map(|user| {
+                ctx.memo.unban(&user.handle);
+                self.unban(&user)
+            }),
+            "kick" => target(0).map(|user| {
+                ctx.memo.leave(&user.handle);
+                self.kick(&user)
+            }),
+            "upload" => args.rest(0).map(|file| self.upload(&file)),
+            "troll" => target(0).map(|user| self.troll(&user)),
+            "pester" => target(0).map(|user| self.pester(&user, ctx.settings.timestamp().as_deref())),
+            "cease" => target(0).map(|user| self.cease(&user, ctx.settings.timestamp().as_deref())),
+            "help" => Some(help(self)),
+            "profile" => Some(profile(self)),
+            _ => self.commands.get(name).and_then(|cmd| cmd.render_in(self, args, ctx)),
+        }
+    }
+
+    pub fn from_name(n: &str) -> Option<Character> {
+        Character::from_name_in(&quirks_dir(), n)
+    }
+
+    /// Loads the character file `n` from the given quirks directory. A file which
+    /// can't be read or fails to parse is reported and treated as missing.
+    pub fn from_name_in(dir: &Path, n: &str) -> Option<Character> {
+        let rel_path_string = format!("./{}.json", n);
+
+        let rel_path = RelativePath::new(rel_path_string.as_str());
+        let full_path = rel_path.to_path(dir);
+        let file_exists: bool = full_path.is_file();
+        if !file_exists {
+            None
+        } else {
+            let data = match fs::read_to_string(&full_path) {
+                Ok(data) => data,
+                Err(err) => {
+                    eprintln!("Ignoring {}.json: {}", n, err);
+                    return None;
+                },
+            };
+
+            let mut c = match parse_character(&data, &load_settings(dir)) {
+                Ok(c) => c,
+                Err(err) => {
+                    eprintln!("Ignoring {}.json: {}", n, err);
+                    return None;
+                },
+            };
+            c.name = n.to_string();
+
+            Some(c)
+        }
+    }
+
+    /// Loads every valid character file in the given quirks directory, sorted by name.
+    /// Files which fail to parse are skipped rather than aborting the whole listing.
+    pub fn all_in(dir: &Path) -> Vec<Character> {
+        let mut characters: Vec<Character> = Vec::new();
+        let settings = load_settings(dir);
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return characters,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = match path.file_stem().and_then(|s| s.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            if path.extension().and_then(|e| e.to_str()) != Some("json") || name.starts_with('_') {
+                continue;
+            }
+            let data = match fs::read_to_string(&path) {
+                Ok(data) => data,
+                Err(_) => continue,
+            };
+            if let Ok(mut c) = parse_character(&data, &settings) {
+                c.name = name;
+                characters.push(c);
+            }
+        }
+        characters.sort_by(|a, b| a.name.cmp(&b.name));
+        characters
+    }
+}
+
+/// Everything an action command may need besides its arguments. Built with
+/// `Context::new`, so that anything added later can come with a default.
+#[non_exhaustive]
+pub struct Context<'a> {
+    /// The quirks directory, used to look up other characters.
+    pub dir: &'a Path,
+    pub settings: &'a Settings,
+    pub memo: &'a mut Memo,
+    characters: OnceCell<Vec<Character>>,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(dir: &'a Path, settings: &'a Settings, memo: &'a mut Memo) -> Context<'a> {
+        Context { dir, settings, memo, characters: OnceCell::new() }
+    }
+
+    /// Every character in the quirks directory, loaded the first time they're needed
+    /// and kept for as long as the context lives.
+    pub fn characters(&self) -> &[Character] {
+        self.characters.get_or_init(|| Character::all_in(self.dir))
+    }
+}
+
+/// The directory character files are loaded from by default, `quirks` in the working directory.
+pub fn quirks_dir() -> PathBuf {
+    current_dir().unwrap().join("quirks")
+}
+
+#[derive(Debug, Default)]
+pub struct Characters {
+    pub dir: PathBuf,
+    pub settings: Settings,
+    pub string: String,
+    pub message: ParsedMessage,
+    pub characters: BTreeMap<String, Character>
+}
+
+impl Characters {
+    pub fn from_string(s: &str) -> Characters {
+        Characters::from_string_in(&quirks_dir(), s)
+    }
+
+    pub fn from_string_in(dir: &Path, s: &str) -> Characters {
+        let mut cs = Characters::from_message_in(dir, ParsedMessage::parse(s));
+        cs.string = s.to_owned();
+        cs
+    }
+
+    /// Loads every character referenced by an already parsed message.
+    pub fn from_message(message: ParsedMessage) -> Characters {
+        Characters::from_message_in(&quirks_dir(), message)
+    }
+
+    pub fn from_message_in(dir: &Path, message: ParsedMessage) -> Characters {
+        let mut characters = BTreeMap::new();
+        let shared_commands = load_shared_commands(dir);
+        for name in message.characters() {
+            if let Some(mut c) = Character::from_name_in(dir, name) {
+                for (cmd_name, cmd) in &shared_commands {
+                    c.commands.entry(cmd_name.clone()).or_insert_with(|| cmd.clone());
+                }
+                characters.insert(name.to_string(), c);
+            }
+        }
+
+        Characters {
+            dir: dir.to_path_buf(),
+            settings: load_settings(dir),
+            string: message.to_string(),
+            message,
+            characters,
+        }
+    }
+
+    pub fn quirked(&self) -> String {
+        self.render(&self.message)
+    }
+
+    /// Like `quirked`, but reading and updating the state of an ongoing memo.
+    pub fn quirked_in(&self, memo: &mut Memo) -> String {
+        self.render_in(&self.message, memo)
+    }
+
+    /// The last loaded character the message speaks or acts as, if any.
+    pub fn speaker(&self) -> Option<&Character> {
+        self.message.lines.iter().rev()
+            .filter_map(|l| l.character())
+            .find_map(|name| self.characters.get(name))
+    }
+
+    /// The status asked for by the last presence command in the message, if any.
+    /// Going offline clears the character's custom status text.
+    pub fn presence(&self) -> Option<PresenceChange> {
+        self.message.lines.iter().rev().find_map(|line| match line {
+            Line::Command { character, name, .. } => {
+                let c = self.characters.get(character)?;
+                let presence = Presence::from_command(name)?;
+                let status = match presence {
+                    Presence::Offline => None,
+                    _ => c.status.clone(),
+                };
+                Some(PresenceChange { presence, status })
+            },
+            _ => None,
+        })
+    }
+
+    /// Renders a message using the characters loaded into this collection.
+    pub fn render(&self, message: &ParsedMessage) -> String {
+        self.render_in(message, &mut Memo::default())
+    }
+
+    pub fn render_in(&self, message: &ParsedMessage, memo: &mut Memo) -> String {
+        // One context for the whole message, so other characters are loaded at most once.
+        let mut ctx = Context::new(&self.dir, &self.settings, memo);
+        let lines: Vec<String> = message.lines.iter().map(|l| self.render_line_with(l, &mut ctx)).collect();
+        lines.join("\n").trim_end().to_string()
+    }
+
+    pub fn render_line(&self, line: &Line) -> String {
+        self.render_line_in(line, &mut Memo::default())
+    }
+
+    /// Renders a single line of a message. Lines which are not addressed to a
+    /// loaded character, or which use an unknown command, are returned unchanged.
+    pub fn render_line_in(&self, line: &Line, memo: &mut Memo) -> String {
+        self.render_line_with(line, &mut Context::new(&self.dir, &self.settings, memo))
+    }
+
+    fn render_line_with(&self, line: &Line, ctx: &mut Context) -> String {
+        match line {
+            Line::Dialogue { character, text } => {
+                if let Some(c) = self.characters.get(character) {
+                    // Speaking from another timeline prefixes the acronym, e.g. `PAA: `.
+                    return match ctx.memo.timeline(character) {
+                        Some(t) => format!("{}{}", t.letter(), c.quirked(text)),
+                        None => c.quirked(text),
+                    };
+                }
+            },
+            Line::Command { character, name, args, .. } => {
+                if let Some(c) = self.characters.get(character) {
+                    if let Some(rendered) = c.command_in(name, args, ctx) {
+                        return rendered;
+                    }
+                }
+            },
+            Line::Global { name, .. } => {
+                match name.as_str() {
+                    "list" => return list(ctx.characters()),
+                    "who" => return who(ctx.memo),
+                    _ => {},
+                }
+            },
+            // The backslash becomes a zero-width space, so the line still isn't quirked
+            // if the message is parsed again, such as after it's edited.
+            Line::Escaped(s) => return format!("\u{200B}{}", s),
+            Line::Plain(_) | Line::CodeFence(_) => {},
+        }
+
+        line.raw()
+    }
+}
+
+pub fn parse_safe(s: String) -> Character {
+    parse_character(&s, &Settings::default()).unwrap()
+}
+
+/// Parses a character file, deriving its acronym from the handle if it has none.
+fn parse_character(s: &str, settings: &Settings) -> serde_json::Result<Character> {
+    let mut c: Character = serde_json::from_str(&strip_jsonc_comments(s, true))?;
+    if c.acronym.is_empty() {
+        c.acronym = derive_acronym(&c.handle, settings.acronym_max_length);
+    }
+    Ok(c)
+}
+
+pub fn capitalize(s: &str) -> String {
+    let mut c = s.chars();
+    match c.next() {
+        None => String::new(),
+        Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
+    }
+}
+
+pub fn invert_capitalization(s: &str) -> String {
+    let c = s.chars();
+    let mut new_string = String::new();
+    for ch in c {
+        if ch.is_uppercase() {
+            new_string = new_string + ch.to_lowercase().to_string().as_str();
+        } else if ch.is_lowercase() {
+            new_string = new_string + ch.to_uppercase().to_string().as_str();
+        } else {
+            new_string = new_string + ch.to_string().as_str();
+        }
+    }
+    new_string
+}
+
+
+/// Takes a string of jsonc content and returns a comment free version
+/// which should parse fine as regular json.
+/// Nested block comments are supported.
+/// preserve_locations will replace most comments with spaces, so that JSON parsing
+/// errors should point to the right location.
+pub fn strip_jsonc_comments(jsonc_input: &str, preserve_locations: bool) -> String {
+    let mut json_output = String::new();
+
+    let mut block_comment_depth: u8 = 0;
+    let mut is_in_string: bool = false; // Comments cannot be in strings
+
+    for line in jsonc_input.split('\n') {
+        let mut last_char: Option<char> = None;
+        for cur_char in line.chars() {
+            // Check whether we're in a string
+            if block_comment_depth == 0 && last_char != Some('\\') && cur_char == '"' {
+                is_in_string = !is_in_string;
+            }
+
+            // Check for line comment start
+            if !is_in_string && last_char == Some('/') && cur_char == '/' {
+                last_char = None;
+                if preserve_locations {
+                    json_output.push_str("  ");
+                }
+                break; // Stop outputting or parsing this line
+            }
+            // Check for block comment start
+            if !is_in_string && last_char == Some('/') && cur_char == '*' {
+                block_comment_depth += 1;
+                last_char = None;
+                if preserve_locations {
+                    json_output.push_str("  ");
+                }
+            // Check for block comment end
+            } else if !is_in_string && last_char == Some('*') && cur_char == '/' {
+                if block_comment_depth > 0 {
+                    block_comment_depth -= 1;
+                }
+                last_char = None;
+                if preserve_locations {
+                    json_output.push_str("  ");
+                }
+            // Output last char if not in any block comment
+            } else {
+                if block_comment_depth == 0 {
+                    if let Some(last_char) = last_char {
+                        json_output.push(last_char);
+                    }
+                } else {
+                    if preserve_locations {
+                        json_output.push_str(" ");
+                    }
+                }
+                last_char = Some(cur_char);
+            }
+        }
+
+        // Add last char and newline if not in any block comment
+        if let Some(last_char) = last_char {
+            if block_comment_depth == 0 {
+                json_output.push(last_char);
+            } else if preserve_locations {
+                json_output.push(' ');
+            }
+        }
+
+        // Remove trailing whitespace from line
+        while json_output.ends_with(' ') {
+            json_output.pop();
+        }
+        json_output.push('\n');
+    }
+
+    json_output
+}
+
+pub fn quirked(s: &str, c: &Character) -> String {
+    let mut new_string;
+    new_string = mutate_line_multi(s, &c.quirks);
+    new_string = format!("{}: {}", c.acronym, new_string);
+    new_string
+}
+
+pub fn mutate_line_multi(s: &str, d: &Vec<BTreeMap<String, Value>>) -> String {
+    let mut new_string = s.to_owned();
+    for quirk in d {
+        new_string = mutate_line(new_string.as_str(), quirk);
+    }
+
+    new_string.to_string()
+}
+
+pub fn mutate_line(s: &str, d: &BTreeMap<String, Value>) -> String {
+    let mut rng = rand::thread_rng();
+    let mut string: String = s.to_owned().clone();
+    let first = d.iter().next().unwrap();
+    let t = first.0.clone();
+    let v = first.1.clone();
+    match t.as_str() {
+        "prefix" => {format!("{}{}", &v.as_str().unwrap(), s)},
+        "suffix" => {format!("{}{}", s, &v.as_str().unwrap())},
+        "simple_replacements" => {
+            for replacement in v.as_array().unwrap() {
+                string = string.replace(replacement[0].as_str().unwrap(), replacement[1].as_str().unwrap());
+            }
+            string
+        },
+        "random_replacements" => {
+            for replacement in v.as_array().unwrap() {
+                let count = string.matches(replacement[0].as_str().unwrap());
+                let mut owned_string = string.clone();
+                for _ in count {
+                    owned_string = owned_string.replacen(replacement[0].as_str().unwrap(), replacement[1].as_array().unwrap().choose(&mut rng).unwrap().as_str().unwrap(), 1);
+                }
+
+                string = owned_string;
+            }
+            string
+        },
+        "regex_replacements" => {
+            for replacement in v.as_array().unwrap() {
+                let re = Regex::new(replacement[0].as_str().unwrap()).unwrap();
+                let after = re.replace_all(string.as_str(), replacement[1].as_str().unwrap());
+                string = after.to_string();
+            }
+            string
+        }
+        "scramble" => {
+            for replacement in v.as_array().unwrap() {
+                let count = string.matches(replacement[0].as_str().unwrap());
+                let mut owned_string = string.clone();
+
+                for _ in count {
+                    let scrambler = replacement[1].as_str().unwrap();
+                    let scrambler_graphemes = scrambler.graphemes(true);
+                    let mut list_graphemes: Vec<&str> = Vec::new();
+                    for g in scrambler_graphemes {
+                        list_graphemes.push(g);
+                    }
+                    let cloned_string = string.to_owned();
+                    let ms = cloned_string.matches(replacement[0].as_str().unwrap());
+                    for m in ms {
+                        list_graphemes.shuffle(&mut rng);
+                        let scrambler = String::from_iter(list_graphemes.to_owned());
+                        owned_string = owned_string.replacen(m, &scrambler, 1);
+                    }
+                }
+
+                string = owned_string;
+            }
+            string
+        },
+        "style" => {
+            match v.as_str().unwrap() {
+                "lowercase" => {s.to_lowercase()},
+                "uppercase" => {s.to_uppercase()},
+                "alternating" => {
+                    let mut new_string = "".to_owned();
+                    let graphemes = string.graphemes(true);
+                    for (i, g) in graphemes.enumerate() {
+                        if i % 2 == 0 {
+                            new_string.push_str(&g.to_uppercase());
+                        } else {
+                            new_string.push_str(&g.to_lowercase());
+                        }
+                    }
+                    new_string
+                },
+                "camelcase" => {
+                    let mut new_string = "".to_owned();
+                    let words = string.split_word_bounds();
+                    for word in words {
+                        new_string.push_str(&capitalize(word));
+                    }
+                    new_string
+                },
+                "reverse" => {
+                    let mut new_string = "".to_owned();
+                    for g in string.graphemes(true).rev() {
+                        new_string.push_str(g);
+                    }
+                    new_string
+                },
+                "inverted" => {
+                    let res = invert_capitalization(string.as_str());
+                    res
+                },
+                &_ => {s.to_string()}
+            }
+        },
+        &_ => {s.to_string()},
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use chrono::Local;
+use serde::{Serialize, Deserialize};
+
+use crate::quirk::strip_jsonc_comments;
+
+/// Name of the file in the quirks directory holding settings shared by every character.
+pub const SETTINGS_FILE: &str = "_settings.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// strftime-style format of the time shown in pestering notices, e.g. `%H:%M`.
+    /// `null` leaves the time out entirely.
+    pub timestamp_format: Option<String>,
+    /// Most letters an acronym made from a handle may have, or `null` for no limit.
+    pub acronym_max_length: Option<usize>,
+    /// Whether `online`, `offline`, `idle` and `unidle` also change the account's
+    /// real status. Used by the bot rather than the quirk engine.
+    pub sync_presence: bool,
+    /// IDs of the servers where our nickname follows the character we speak as.
+    pub nickname_servers: Vec<u64>,
+    /// Least number of seconds between two nickname changes in the same server.
+    pub nickname_cooldown: u64,
+    /// How the bot puts quirked messages in the channel.
+    pub delivery: Delivery,
+    /// What reacting to one of our quirked messages with each emoji does. Custom
+    /// emoji are given by name.
+    pub reactions: HashMap<String, ReactionAction>,
+}
+
+/// Ways of replacing a message with its quirked version.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Delivery {
+    /// Edit the original message in place.
+    #[default]
+    Edit,
+    /// Delete the original message and send the quirked text as ourselves.
+    Resend,
+    /// Delete the original message and post the quirked text through a webhook,
+    /// under the character's handle and avatar.
+    Webhook,
+}
+
+/// Things a reaction can do to a message the bot quirked.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReactionAction {
+    /// Put back the text we typed.
+    Undo,
+    /// Quirk the text we typed again, for different random replacements and scrambles.
+    Reroll,
+    /// Quirk the dialogue as the character loaded from the given name instead.
+    Quirk(String),
+    Delete,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            timestamp_format: Some("%H:%M".to_string()),
+            acronym_max_length: None,
+            sync_presence: false,
+            nickname_servers: Vec::new(),
+            nickname_cooldown: 60,
+            delivery: Delivery::Edit,
+            reactions: HashMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// The current local time in the configured format. An invalid format is
+    /// treated the same as no format at all.
+    pub fn timestamp(&self) -> Option<String> {
+        let format = self.timestamp_format.as_ref()?;
+        let mut timestamp = String::new();
+        match write!(timestamp, "{}", Local::now().format(format)) {
+            Ok(_) => Some(timestamp),
+            Err(_) => None,
+        }
+    }
+}
+
+/// Loads the settings file from the given quirks directory, falling back to the
+/// defaults if there isn't one. A file which fails to parse is reported and the
+/// defaults are used instead.
+pub fn load_settings(dir: &Path) -> Settings {
+    let data = match fs::read_to_string(dir.join(SETTINGS_FILE)) {
+        Ok(data) => data,
+        Err(_) => return Settings::default(),
+    };
+    match serde_json::from_str(&strip_jsonc_comments(&data, true)) {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("Ignoring {}: {}", SETTINGS_FILE, err);
+            Settings::default()
+        },
+    }
+}
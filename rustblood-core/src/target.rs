@@ -0,0 +1,46 @@
+use std::path::Path;
+
+use crate::acronym::derive_acronym;
+use crate::quirk::Character;
+use crate::settings::Settings;
+
+/// Someone an action command is aimed at, such as the user being kicked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub handle: String,
+    pub acronym: String,
+}
+
+impl Target {
+    /// A target who isn't one of our characters, with an acronym derived from their handle.
+    pub fn derived(user: &str, settings: &Settings) -> Target {
+        Target {
+            handle: user.to_string(),
+            acronym: derive_acronym(user, settings.acronym_max_length),
+        }
+    }
+
+    pub fn from_character(c: &Character) -> Target {
+        Target {
+            handle: c.handle.clone(),
+            acronym: c.acronym.clone(),
+        }
+    }
+
+    /// Looks `user` up among the character files in `dir` by file name, alias or
+    /// handle, ignoring case, so that `kick KARKAT` kicks carcinoGeneticist [CG].
+    /// Unknown names fall back to `Target::derived`.
+    pub fn resolve(dir: &Path, user: &str, settings: &Settings) -> Target {
+        Target::resolve_among(&Character::all_in(dir), user, settings)
+    }
+
+    /// Like `resolve`, but among characters which were already loaded.
+    pub fn resolve_among(characters: &[Character], user: &str, settings: &Settings) -> Target {
+        let matches = |s: &str| s.eq_ignore_ascii_case(user);
+        characters
+            .iter()
+            .find(|c| matches(&c.name) || matches(&c.handle) || c.aliases.iter().any(|a| matches(a)))
+            .map(Target::from_character)
+            .unwrap_or_else(|| Target::derived(user, settings))
+    }
+}
@@ -0,0 +1,1764 @@
+/// The sample quirks folder at the root of the repository.
+#[cfg(test)]
+fn quirks_dir() -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../quirks")
+}
+
+#[cfg(test)]
+use crate::testing::TempDir;
+
+#[cfg(test)]
+mod quirks {
+    use super::{TempDir, quirks_dir};
+    use crate::quirk::{self, Characters};
+    use crate::quirk::Character;
+    use test_case::test_case;
+
+    #[test_case(false ; "without unicode")]
+    #[test_case(true ; "with unicode")]
+    fn can_serialize_deserialize(unicode: bool) {
+        let data;
+        if !unicode {
+            data = r#"
+            // 'quirks' is an array of dicts that describes operations to be done on a string. Operations are done in the order these quirks are described in.
+            // 'handle' is just a convenience for the script to display for debugging.
+            // 'acronym' is used to construct the final message. Allows for characters with the same acronym to be used, just with different filenames to reference them. Optional.
+            //
+            // Possible quirks:
+            //                  prefix:
+            //                  Adds the following characters to the beginning of the string.
+            //
+            //                  suffix:
+            //                  Adds the following characters to the end of the string.
+            //
+            //                  simple_replacements:
+            //                  For each replacement operation described, changes all occurances of the supplied character sequences inside the string.
+            //                  Operations occur in their described order.
+            //
+            //                  random_replacements:
+            //                  For each replacement operation described, replaces all occurances of the supplied character sequence inside the string with a random selection.
+            //                  Operations occut in their described order.
+            //
+            //                  scramble:
+            //                  For each operation described, changes all occurances of the supplied character sequence inside the string with a random arrangement of the characters supplied.
+            //                  Operations occur in their described order.
+            //
+            //                  style:
+            //                  Describes a typing style. If this is specified, the script will attempt to apply special operations.
+            //                  Supported values: lowercase, uppercase, alternating, camelcase, reverse, inverted
+            //
+            //                  WARNING!!! Make sure to only include the following structure in your quirk file. Including these comments inside the file will render it inoperable.
+            {
+                "handle": "yourHandle",
+                "acronym": "YH",
+                "quirks": [
+                    {
+                        "prefix": ">>> "
+                    },
+                    {
+                        "simple_replacements": [
+                            [
+                                "z", "zee"
+                            ]
+                        ]
+                    },
+                    {
+                        "random_replacements": [
+                            [
+                                "lmao", ["laughing my ass off", "assing my laugh off", "hahahaha"] // lol
+                            ]
+                        ]
+                    },
+                    {
+                        "scramble": [
+                            [
+                                "fuck", "%&#@"
+                            ],
+                            [
+                                "shit", "%&!@"
+                            ],
+                            [
+                                "ass", "@$%"
+                            ]
+                        ]
+                    },
+                    {
+                        "suffix": " <<<"
+                    },
+                    {
+                        "style": "lowercase"
+                    }
+                ]
+            }
+            "#;
+        } else {
+            data = r#"
+            {
+                // Comment
+                // Test goes
+                // Here
+                "handle": "lowhungNoose",
+                "acronym": "LN",
+                "quirks": [
+                    {
+                        "prefix": "C>lll= "
+                    },
+                    {
+                        "simple_replacements": [
+                            [
+                                "A", "Д"
+                            ],
+                            [
+                                "a", "д"
+                            ],
+                            [
+                                "E", "Э"
+                            ],
+                            [
+                                "e", "э"
+                            ],
+                            [
+                                "B", "В"
+                            ],
+                            [
+                                "b", "ѣ"
+                            ],
+                            [
+                                "r","г"
+                            ],
+                            [
+                                "R", "Я"
+                            ],
+                            [
+                                "W", "Ш"
+                            ],
+                            [
+                                "T", "Т"
+                            ],
+                            [
+                                "t", "т"
+                            ],
+                            [
+                                "O", "Ø"
+                            ],
+                            [
+                                "V", "Ѵ"
+                            ],
+                            [
+                                "v", "ѵ"
+                            ],
+                            [
+                                "io", "Ю"
+                            ],
+                            [
+                                "X", "Ж"
+                            ],
+                            [
+                                "x", "ж"
+                            ],
+                            [
+                                "N", "И"
+                            ],
+                            [
+                                "n", "л"
+                            ],
+                            [
+                                "m", "м"
+                            ],
+                            [
+                                "Y", "Ч"
+                            ],
+                            [ 
+                                "y", "ч"
+                            ]
+                        ]
+                    },
+                    {
+                        "suffix": " ==" // Comment test
+                    }
+                ]
+            }
+            "#;
+        }
+        
+    
+        let c = quirk::parse_safe(data.to_string());
+    
+        let j = serde_json::to_string_pretty(&c).unwrap();
+    
+        assert!(c.quirks.len() > 0); // Our quirks vec actually has content
+        if unicode {
+            assert!(c.quirks[0]["prefix"] == "C>lll= "); // The first element of quirks is what we expect
+            assert!(c.quirks[2]["suffix"] == " =="); // The last element of quirks is what we expect
+        } else {
+            assert!(c.quirks[0]["prefix"] == ">>> "); // The first element of quirks is what we expect
+            assert!(c.quirks[5]["style"] == "lowercase"); // The last element of quirks is what we expect
+        }
+        assert!(quirk::parse_safe(j) == c); // The values did not mutate for no reason
+    
+    }
+    
+    #[test_case(">>> " ; "without unicode")]
+    #[test_case("𝖟∞ " ; "with unicode")]
+    fn can_prefix(s: &str) {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "quirks": [
+                {
+                    "prefix": ""
+                }
+            ]
+        }
+        "#;
+        let data = data.replace("\"\"", &format!("\"{}\"", s));
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = "The quick brown fox jumped over the lazy dog.";
+    
+        let mutated_string = c.quirked(test_string);
+    
+        assert!(mutated_string == format!("YH: {}The quick brown fox jumped over the lazy dog.", s));
+    }
+    
+    #[test_case(" <<<" ; "without unicode")]
+    #[test_case(" 𝖟∞" ; "with unicode")]
+    fn can_suffix(s: &str) {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "quirks": [
+                {
+                    "suffix": ""
+                }
+            ]
+        }
+        "#;
+        let data = data.replace("\"\"", &format!("\"{}\"", s));
+
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = "The quick brown fox jumped over the lazy dog.";
+    
+        let mutated_string = c.quirked(test_string);
+    
+        assert!(mutated_string == format!("YH: The quick brown fox jumped over the lazy dog.{}", s));
+    }
+    
+    #[test_case("zee" ; "without unicode")]
+    #[test_case("z𝖟𝖟" ; "with unicode")]
+    fn can_simple_replace(s: &str) {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "quirks": [
+                {
+                    "simple_replacements": [
+                        [
+                            "z", ""
+                        ],
+                        [
+                            "The", "Da"
+                        ]
+                    ]
+                }
+            ]
+        }
+        "#;
+        let data = data.replace("\"\"", &format!("\"{}\"", s));
+
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = "The quick brown fox jumped over the lazy dog.";
+    
+        let mutated_string = c.quirked(test_string);
+        
+        assert!(mutated_string == format!("YH: Da quick brown fox jumped over the la{}y dog.", s));
+    }
+    
+    #[test_case("red", "blue", "green" ; "without unicode")]
+    #[test_case("𝓻𝖟𝓭", "𝓫𝓵𝓊𝓮", "𝓰𝓻𝓮𝓮𝓷" ; "with unicode")]
+    fn can_random_replace(s1: &str, s2: &str, s3: &str) {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "quirks": [
+                {
+                    "random_replacements": [
+                        [
+                            "brown", ["s1", "s2", "s3"]
+                        ]
+                    ]
+                }
+            ]
+        }
+        "#;
+
+        let data = data.replace("\"s1\"", &format!("\"{}\"", s1));
+        let data = data.replace("\"s2\"", &format!("\"{}\"", s2));
+        let data = data.replace("\"s3\"", &format!("\"{}\"", s3));
+
+
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = "The quick brown fox jumped over the lazy dog.";
+    
+        let mutated_string = c.quirked(test_string);
+    
+        assert!(mutated_string == format!("YH: The quick {} fox jumped over the lazy dog.", s1) ||
+                mutated_string == format!("YH: The quick {} fox jumped over the lazy dog.", s2) ||
+                mutated_string == format!("YH: The quick {} fox jumped over the lazy dog.", s3));
+    }
+    
+    #[test_case("(?P<y>\\\\d{4})-(?P<m>\\\\d{2})-(?P<d>\\\\d{2})", "$m/$d/$y" ; "without unicode")]
+    #[test_case("(?P<y>\\\\d{4})-(?P<m>\\\\d{2})-(?P<d>\\\\d{2})", "$m𝓵$d𝓵$y" ; "with unicode")]
+    fn can_regex_replace(s1: &str, s2: &str) {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "quirks": [
+                {
+                    "regex_replacements": [
+                        [
+                            "s1", "s2"
+                        ]
+                    ]
+                }
+            ]
+        }
+        "#;
+
+        let data = data.replace("\"s1\"", &format!("\"{}\"", s1));
+        let data = data.replace("\"s2\"", &format!("\"{}\"", s2));
+
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = "The quick 2014-01-01 brown fox jumped over the lazy dog.";
+    
+        let mutated_string = c.quirked(test_string);
+    
+        assert!(mutated_string == "YH: The quick 01/01/2014 brown fox jumped over the lazy dog." ||
+                mutated_string == "YH: The quick 01𝓵01𝓵2014 brown fox jumped over the lazy dog.");
+    }
+
+    #[test_case("red", "blue", "green", false ; "without unicode")]
+    #[test_case("𝓻𝖟𝓭", "𝓫𝓵𝓊𝓮", "𝓰𝓻𝓮𝓮𝓷", true ; "with unicode")]
+    fn can_scramble(s1: &str, s2: &str, s3: &str, unicode: bool) {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "quirks": [
+                {
+                    "scramble": [
+                        [
+                            "quick", "s1"
+                        ],
+                        [
+                            "jumped", "s2"
+                        ],
+                        [
+                            "lazy", "s3"
+                        ]
+                    ]
+                }
+            ]
+        }
+        "#;
+        let data = data.replace("\"s1\"", &format!("\"{}\"", s1));
+        let data = data.replace("\"s2\"", &format!("\"{}\"", s2));
+        let data = data.replace("\"s3\"", &format!("\"{}\"", s3));
+
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = "The quick brown fox jumped over the lazy dog.";
+    
+        let mutated_string = c.quirked(test_string);
+        
+        assert!(!(mutated_string.contains("quick")) && !(mutated_string.contains("jumped") && !(mutated_string.contains("lazy"))));
+        if unicode {
+            assert!(mutated_string.contains("𝖟") || mutated_string.contains("𝓵") || mutated_string.contains("𝓰"));
+        } 
+    }
+    
+    #[test_case("The quick brown fox jumped over the lazy dog." ; "without unicode")]
+    #[test_case("Тhe quick brown fox jumped over the lazy dog." ; "with unicode")]
+    fn can_lowercase_style(s: &str) {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "quirks": [
+                {
+                    "style": "lowercase"
+                }
+            ]
+        }
+        "#;
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = format!("{}", s);
+    
+        let mutated_string = c.quirked(test_string.as_str());
+        
+        assert!(mutated_string == "YH: the quick brown fox jumped over the lazy dog." ||
+                mutated_string == "YH: тhe quick brown fox jumped over the lazy dog.");    
+    }
+    
+    #[test_case("The quick brown fox jumped over the lazy dog." ; "without unicode")]
+    #[test_case("ThЭ quick brown fox jumped over the lazy dog." ; "with unicode")]
+    fn can_uppercase_style(s: &str) {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "quirks": [
+                {
+                    "style": "uppercase"
+                }
+            ]
+        }
+        "#;
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = format!("{}", s);
+    
+        let mutated_string = c.quirked(test_string.as_str());
+        
+        assert!(mutated_string == "YH: THE QUICK BROWN FOX JUMPED OVER THE LAZY DOG." ||
+                mutated_string == "YH: THЭ QUICK BROWN FOX JUMPED OVER THE LAZY DOG."); 
+    }
+
+    #[test_case("The quick brown fox jumped over the lazy dog." ; "without unicode")]
+    #[test_case("ThЭ quick brown fox jumped over the lazy dog." ; "with unicode")]
+    fn can_alternating_style(s: &str) {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "quirks": [
+                {
+                    "style": "alternating"
+                }
+            ]
+        }
+        "#;
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = format!("{}", s);
+    
+        let mutated_string = c.quirked(test_string.as_str());
+        
+        assert!(mutated_string == "YH: ThE QuIcK BrOwN FoX JuMpEd oVeR ThE LaZy dOg." ||
+                mutated_string == "YH: ThЭ QuIcK BrOwN FoX JuMpEd oVeR ThE LaZy dOg."); 
+    }
+
+    #[test_case("The quick brown fox jumped over the lazy dog." ; "without unicode")]
+    #[test_case("ThЭ quick brown fox jumped over the lazy dog." ; "with unicode")]
+    fn can_camelcase_style(s: &str) {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "quirks": [
+                {
+                    "style": "camelcase"
+                }
+            ]
+        }
+        "#;
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = format!("{}", s);
+    
+        let mutated_string = c.quirked(test_string.as_str());
+        
+        assert!(mutated_string == "YH: The Quick Brown Fox Jumped Over The Lazy Dog." ||
+                mutated_string == "YH: ThЭ Quick Brown Fox Jumped Over The Lazy Dog."); 
+    }
+
+    #[test_case("The quick brown fox jumped over the lazy dog." ; "without unicode")]
+    #[test_case("ThЭ quick brown fox jumped over the lazy dog." ; "with unicode")]
+    fn can_reverse_style(s: &str) {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "quirks": [
+                {
+                    "style": "reverse"
+                }
+            ]
+        }
+        "#;
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = format!("{}", s);
+    
+        let mutated_string = c.quirked(test_string.as_str());
+        
+        assert!(mutated_string == "YH: .god yzal eht revo depmuj xof nworb kciuq ehT" ||
+                mutated_string == "YH: .god yzal eht revo depmuj xof nworb kciuq ЭhT"); 
+    }
+
+    #[test_case("The quick brown fox jumped over the lazy dog." ; "without unicode")]
+    #[test_case("ThЭ quick brown fox jumped over the lazy dog." ; "with unicode")]
+    fn can_inverted_style(s: &str) {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "quirks": [
+                {
+                    "style": "inverted"
+                }
+            ]
+        }
+        "#;
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = format!("{}", s);
+    
+        let mutated_string = c.quirked(test_string.as_str());
+        
+        assert!(mutated_string == "YH: tHE QUICK BROWN FOX JUMPED OVER THE LAZY DOG." ||
+                mutated_string == "YH: tHэ QUICK BROWN FOX JUMPED OVER THE LAZY DOG."); 
+    }
+
+    #[test]
+    fn can_combine_quirks() {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "quirks": [
+                {
+                    "style": "reverse"
+                },
+                {
+                    "style": "uppercase"
+                }
+            ]
+        }
+        "#;
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = "The quick brown fox jumped over the lazy dog.";
+    
+        let mutated_string = c.quirked(test_string);
+        
+        assert!(mutated_string == "YH: .GOD YZAL EHT REVO DEPMUJ XOF NWORB KCIUQ EHT"); 
+    }
+
+    #[test_case( "" ; "Trial one")]
+    #[test_case( "" ; "Trial two")]
+    #[test_case( "" ; "Trial three")]
+    #[test_case( "" ; "Trial four")]
+    #[test_case( "" ; "Trial five")]
+    fn quirks_are_deterministic(_s: &str) {
+        let data = r#"
+        {
+            "handle": "syntaxError",
+            "acronym": "`SE",
+            "quirks": [
+                {
+                    "style": "lowercase"
+                },
+                {
+                    "prefix": ">_ "
+                },
+                {
+                    "suffix": "`"
+                },
+                {
+                    "simple_replacements": [
+                        [
+                            "lorem", "[<censor>]"
+                        ],
+                        [
+                            "ipsum", "[<censor>]"
+                        ],
+                        [
+                            "dolar", "[<censor>]"
+                        ],
+                        [
+                            "somet", "[<mini-censor>]"
+                        ]
+                    ]
+                },
+                {
+                    "simple_replacements": [
+                        [
+                            "<mini-censor>", "▜▟"
+                        ],
+                        [
+                            "<censor>", "▛▜▟▙"
+                        ]
+                    ]
+                },
+                {
+                    "simple_replacements": [
+                        [
+                            ".", ""
+                        ],
+                        [
+                            ",", ""
+                        ],
+                        [
+                            "'", ""
+                        ],
+                        [
+                            "ing", "in"
+                        ],
+                        [
+                            "l", "1"
+                        ],
+                        [
+                            "i", "1"
+                        ],
+                        [
+                            "o", "0"
+                        ]
+                    ]
+                }
+            ]
+        }
+        "#;
+        let c = quirk::parse_safe(data.to_string());
+        let test_string = "The lorem dolar fox jumped ipsum the somet dog.";
+    
+        let mutated_string = c.quirked(test_string);
+        
+        assert!(mutated_string == "`SE: >_ the [▛▜▟▙] [▛▜▟▙] f0x jumped [▛▜▟▙] the [▜▟] d0g`");
+    }
+
+    #[test]
+    fn can_get_character_from_name() {
+        let c = Character::from_name_in(&quirks_dir(), "ARADIA");
+        if !c.is_some() {
+            panic!("Character quirk file could not be loaded.")
+        }
+    }
+
+    #[test]
+    fn cant_get_character_from_invalid_name() {
+        let c = Character::from_name_in(&quirks_dir(), "MISSING");
+        if c.is_some() {
+            panic!("Missing character quirk file should not return a character")
+        }
+    }
+
+    #[test]
+    fn unreadable_character_file_is_ignored() {
+        let dir = TempDir::new("unreadable-character");
+        std::fs::write(dir.join("BINARY.json"), [0xff, 0xfe, 0x00]).unwrap();
+        assert!(Character::from_name_in(&dir, "BINARY").is_none());
+    }
+
+    #[test]
+    fn can_quirk_multiline() {
+        let string = r#"ARADIA: Lorem ipsum dolar somet.
+NOTRADIA: Nothing to see here."#;
+
+        let expected_string = r#"AA: lorem ipsum dolar somet
+NOTRADIA: Nothing to see here."#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_join_memo() {
+        let string = "ARADIA! join";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] has joined the memo! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+
+    #[test]
+    fn can_leave_memo() {
+        let string = "ARADIA! leave";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] has left the memo! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_go_online() {
+        let string = "ARADIA! online";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] is now online! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_go_offline() {
+        let string = "ARADIA! offline";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] is now offline! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_go_idle() {
+        let string = "ARADIA! idle";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] is now idle! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_go_unidle() {
+        let string = "ARADIA! unidle";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] is no longer idle! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_block() {
+        let string = "ARADIA! block ectoBiologist";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] has blocked ectoBiologist [EB]! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_unblock() {
+        let string = "ARADIA! unblock ectoBiologist";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] has unblocked ectoBiologist [EB]! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_upload() {
+        let string = "ARADIA! upload test.png";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] has uploaded "test.png" --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_kick() {
+        let string = "ARADIA! kick ectoBiologist";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] has kicked ectoBiologist [EB] from the memo! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_ban() {
+        let string = "ARADIA! ban ectoBiologist";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] has banned ectoBiologist [EB] from the memo! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_unban() {
+        let string = "ARADIA! unban ectoBiologist";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] has unbanned ectoBiologist [EB] from the memo! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_troll() {
+        let string = "ARADIA! troll ectoBiologist";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] has begun trolling ectoBiologist [EB]! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_upload_with_spaces() {
+        let string = "ARADIA! upload my cool file.png";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] has uploaded "my cool file.png" --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_kick_quoted_name() {
+        let string = r#"ARADIA! kick  "ecto Biologist""#;
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] has kicked ecto Biologist [EB] from the memo! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn can_quirk_commands_and_dialogue() {
+        let string = r#"ARADIA! join
+ARADIA: Hello, everyone.
+ARADIA! kick ectoBiologist
+Just a plain line.
+KARKAT: What the hell.
+ARADIA! dance
+ARADIA! leave"#;
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] has joined the memo! --
+```
+AA: hello everyone
+```
+-- apocalypseArisen [AA] has kicked ectoBiologist [EB] from the memo! --
+```
+Just a plain line.
+CG: WHAT THE HELL.
+ARADIA! dance
+```
+-- apocalypseArisen [AA] has left the memo! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn missing_argument_leaves_line_unchanged() {
+        let string = "ARADIA! kick\nARADIA: Still here.";
+        let expected_string = "ARADIA! kick\nAA: still here";
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+}
+
+#[cfg(test)]
+mod args {
+    use crate::args::{Args, parse_command};
+    use test_case::test_case;
+
+    #[test_case("one two three", &["one", "two", "three"] ; "plain words")]
+    #[test_case("  one   two  ", &["one", "two"] ; "repeated whitespace")]
+    #[test_case(r#"one "two three" four"#, &["one", "two three", "four"] ; "quoted argument")]
+    #[test_case("say \"\\\"hi\\\" \\\\o/\"", &["say", "\"hi\" \\o/"] ; "escaped quotes")]
+    #[test_case("C:\\files\\a.png", &["C:\\files\\a.png"] ; "lone backslashes")]
+    #[test_case(r#"a"b c"d"#, &["ab cd"] ; "adjacent quotes")]
+    #[test_case(r#""" x"#, &["", "x"] ; "empty quotes")]
+    #[test_case(r#""unterminated arg"#, &["unterminated arg"] ; "unterminated quote")]
+    fn can_tokenize(s: &str, expected: &[&str]) {
+        let args = Args::parse(s);
+        assert_eq!(args.iter().collect::<Vec<&str>>(), expected);
+    }
+
+    #[test]
+    fn can_get_rest_of_line() {
+        let args = Args::parse("my  cool file.png ");
+        assert_eq!(args.rest(0), Some("my  cool file.png".to_string()));
+        assert_eq!(args.rest(2), Some("file.png".to_string()));
+        assert_eq!(args.rest(3), None);
+    }
+
+    #[test]
+    fn rest_of_line_unquotes_last_argument() {
+        let args = Args::parse(r#""my cool file.png""#);
+        assert_eq!(args.rest(0), Some("my cool file.png".to_string()));
+    }
+
+    #[test]
+    fn can_parse_command() {
+        let (name, args) = parse_command("upload   my file.png");
+        assert_eq!(name, "upload");
+        assert_eq!(args.len(), 2);
+        assert_eq!(args.get(0), Some("my"));
+
+        let (name, args) = parse_command("join");
+        assert_eq!(name, "join");
+        assert!(args.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod message {
+    use super::quirks_dir;
+    use crate::args::Args;
+    use crate::message::{Line, ParsedMessage};
+    use crate::quirk::Characters;
+    use test_case::test_case;
+
+    #[test]
+    fn can_parse_lines() {
+        let string = r#"ARADIA: Hello.
+ARADIA! kick "ecto Biologist"
+Just a plain line.
+\ARADIA: Not quirked.
+```
+ARADIA: Inside a code block.
+```"#;
+
+        let message = ParsedMessage::parse(string);
+        assert_eq!(message.lines, vec![
+            Line::Dialogue { character: "ARADIA".to_string(), text: "Hello.".to_string() },
+            Line::Command {
+                character: "ARADIA".to_string(),
+                name: "kick".to_string(),
+                args: Args::parse(r#""ecto Biologist""#),
+                raw: r#"ARADIA! kick "ecto Biologist""#.to_string(),
+            },
+            Line::Plain("Just a plain line.".to_string()),
+            Line::Escaped("ARADIA: Not quirked.".to_string()),
+            Line::CodeFence("```".to_string()),
+            Line::CodeFence("ARADIA: Inside a code block.".to_string()),
+            Line::CodeFence("```".to_string()),
+        ]);
+        assert_eq!(message.to_string(), string);
+    }
+
+    #[test_case("Wow! that's  great" ; "spaces in unknown command")]
+    #[test_case("Hey!  hi there" ; "spaces after exclamation")]
+    #[test_case("!play   song" ; "spaces in global command")]
+    #[test_case("ARADIA!   kick  \"ecto Biologist\" " ; "spaces in known command")]
+    fn keeps_commands_as_typed(string: &str) {
+        assert_eq!(ParsedMessage::parse(string).to_string(), string);
+    }
+
+    #[test]
+    fn leaves_unknown_commands_as_typed() {
+        let string = "Wow! that's  great\n!play   song\nARADIA! dance  wildly";
+        assert_eq!(Characters::from_string_in(&quirks_dir(), string).quirked(), string);
+    }
+
+    #[test]
+    fn can_list_referenced_characters() {
+        let message = ParsedMessage::parse("KARKAT: Hey.\nARADIA! join\nKARKAT: Hey again.");
+        assert_eq!(message.characters(), vec!["KARKAT", "ARADIA"]);
+    }
+
+    #[test]
+    fn single_line_code_block_does_not_open_block() {
+        let message = ParsedMessage::parse("```inline```\nARADIA: Hello.");
+        assert_eq!(message.lines[0], Line::CodeFence("```inline```".to_string()));
+        assert_eq!(message.lines[1], Line::Dialogue { character: "ARADIA".to_string(), text: "Hello.".to_string() });
+    }
+
+    #[test]
+    fn backslash_without_prefix_is_plain() {
+        let message = ParsedMessage::parse("\\o/");
+        assert_eq!(message.lines[0], Line::Plain("\\o/".to_string()));
+    }
+
+    #[test]
+    fn does_not_quirk_code_blocks_or_escaped_lines() {
+        let string = r#"\ARADIA: Not quirked.
+```
+ARADIA: Inside a code block.
+```
+ARADIA: Quirked."#;
+        let expected_string = r#"ARADIA: Not quirked.
+```
+ARADIA: Inside a code block.
+```
+AA: quirked"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        assert_eq!(cs.quirked(), format!("\u{200B}{}", expected_string));
+    }
+
+    #[test]
+    fn escaped_lines_stay_unquirked_when_quirked_again() {
+        let quirked = Characters::from_string_in(&quirks_dir(), "ARADIA: Hello.\n\\KARKAT: Not quirked.").quirked();
+        assert_eq!(quirked, "AA: hello\n\u{200B}KARKAT: Not quirked.");
+        assert_eq!(ParsedMessage::parse(&quirked).lines[1], Line::Plain("\u{200B}KARKAT: Not quirked.".to_string()));
+        assert_eq!(Characters::from_string_in(&quirks_dir(), &quirked).quirked(), quirked);
+    }
+
+    #[test]
+    fn can_render_transformed_message() {
+        let mut message = ParsedMessage::parse("ARADIA: Hello.");
+        message.lines.push(Line::Command {
+            character: "ARADIA".to_string(),
+            name: "leave".to_string(),
+            args: Args::default(),
+            raw: "ARADIA! leave".to_string(),
+        });
+
+        let cs = Characters::from_message_in(&quirks_dir(), message.clone());
+        assert_eq!(cs.render(&message), "AA: hello\n```\n-- apocalypseArisen [AA] has left the memo! --\n```");
+    }
+}
+
+#[cfg(test)]
+mod commands {
+    use std::fs;
+
+    use super::{TempDir, quirks_dir};
+    use crate::memo::Memo;
+    use crate::quirk::{self, Characters, Context};
+    use crate::settings::Settings;
+
+    #[test]
+    fn can_run_custom_command() {
+        let string = "TEREZI! sniff ectoBiologist";
+        let expected_string = 
+r#"```
+-- gallowsCalibrator [GC] sniffs out ectoBiologist [EB]! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn commands_run_without_context() {
+        let c = Characters::from_string_in(&quirks_dir(), "TEREZI: hi").characters.remove("TEREZI").unwrap();
+        let (name, args) = crate::args::parse_command("sniff ectoBiologist");
+        assert_eq!(c.command("online", &Default::default()).unwrap(), "```\n-- gallowsCalibrator [GC] is now online! --\n```");
+        assert_eq!(c.commands[&name].render(&c, &args).unwrap(), "```\n-- gallowsCalibrator [GC] sniffs out ectoBiologist [EB]! --\n```");
+    }
+
+    #[test]
+    fn knows_its_commands() {
+        let c = Characters::from_string_in(&quirks_dir(), "TEREZI: hi").characters.remove("TEREZI").unwrap();
+        assert!(c.has_command("join"));
+        assert!(c.has_command("sniff"));
+        assert!(!c.has_command("dance"));
+    }
+
+    #[test]
+    fn custom_command_requires_arguments() {
+        let string = "TEREZI! sniff";
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, string);
+    }
+
+    #[test]
+    fn custom_command_last_argument_takes_rest_of_line() {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "commands": {
+                "give": {
+                    "args": ["user", "item"],
+                    "template": "-- {handle} [{acronym}] gives {user} [{user.acronym}] {item} --"
+                }
+            },
+            "quirks": []
+        }
+        "#;
+        let c = quirk::parse_safe(data.to_string());
+        let (name, args) = crate::args::parse_command("give ectoBiologist a fancy hat");
+
+        let (dir, settings, mut memo) = (quirks_dir(), Settings::default(), Memo::default());
+        let mut ctx = Context::new(&dir, &settings, &mut memo);
+        assert_eq!(c.command_in(&name, &args, &mut ctx).unwrap(), "```\n-- yourHandle [YH] gives ectoBiologist [EB] a fancy hat --\n```");
+    }
+
+    #[test]
+    fn unused_arguments_are_still_required() {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "commands": {
+                "nod": {
+                    "args": ["user", "reason"],
+                    "template": "-- {handle} nods at {user} --"
+                }
+            },
+            "quirks": []
+        }
+        "#;
+        let c = quirk::parse_safe(data.to_string());
+
+        let (dir, settings, mut memo) = (quirks_dir(), Settings::default(), Memo::default());
+        let mut ctx = Context::new(&dir, &settings, &mut memo);
+        let (name, args) = crate::args::parse_command("nod KARKAT ARADIA");
+        assert_eq!(c.command_in(&name, &args, &mut ctx).unwrap(), "```\n-- yourHandle nods at carcinoGeneticist --\n```");
+        let (name, args) = crate::args::parse_command("nod KARKAT");
+        assert_eq!(c.command_in(&name, &args, &mut ctx), None);
+    }
+
+    #[test]
+    fn context_loads_characters_once() {
+        let dir = TempDir::new("context-characters");
+        fs::copy(quirks_dir().join("KARKAT.json"), dir.join("KARKAT.json")).unwrap();
+        let (settings, mut memo) = (Settings::default(), Memo::default());
+        let ctx = Context::new(&dir, &settings, &mut memo);
+        assert_eq!(ctx.characters().len(), 1);
+
+        fs::copy(quirks_dir().join("ARADIA.json"), dir.join("ARADIA.json")).unwrap();
+        assert_eq!(ctx.characters().len(), 1);
+    }
+
+    #[test]
+    fn builtin_commands_take_priority() {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "commands": {
+                "join": {
+                    "template": "-- nope --"
+                }
+            },
+            "quirks": []
+        }
+        "#;
+        let c = quirk::parse_safe(data.to_string());
+        let (name, args) = crate::args::parse_command("join");
+
+        let (dir, settings, mut memo) = (quirks_dir(), Settings::default(), Memo::default());
+        let mut ctx = Context::new(&dir, &settings, &mut memo);
+        assert_eq!(c.command_in(&name, &args, &mut ctx).unwrap(), "```\n-- yourHandle [YH] has joined the memo! --\n```");
+    }
+
+    #[test]
+    fn can_load_shared_commands() {
+        let dir = TempDir::new("shared-commands");
+        fs::write(dir.join("YOU.json"), r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "commands": {
+                "wave": {
+                    "args": ["user"],
+                    "template": "-- {handle} waves at {user} --"
+                }
+            },
+            "quirks": []
+        }
+        "#).unwrap();
+        fs::write(dir.join("_commands.json"), r#"
+        {
+            // Comments are allowed here too.
+            "hug": {
+                "args": ["user"],
+                "template": "-- {handle} [{acronym}] hugs {user} --"
+            },
+            "wave": {
+                "args": ["user"],
+                "template": "-- overridden by the character --"
+            }
+        }
+        "#).unwrap();
+
+        let cs = Characters::from_string_in(&dir, "YOU! hug ectoBiologist\nYOU! wave ectoBiologist");
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, "```\n-- yourHandle [YH] hugs ectoBiologist --\n```\n```\n-- yourHandle waves at ectoBiologist --\n```");
+    }
+
+    #[test]
+    fn ignores_broken_shared_commands() {
+        let dir = TempDir::new("broken-commands");
+        fs::write(dir.join("YOU.json"), r#"{ "handle": "yourHandle", "acronym": "YH", "quirks": [] }"#).unwrap();
+        fs::write(dir.join("_commands.json"), "{ \"hug\": ").unwrap();
+
+        let cs = Characters::from_string_in(&dir, "YOU! hug ectoBiologist\nYOU! join");
+        assert_eq!(cs.quirked(), "YOU! hug ectoBiologist\n```\n-- yourHandle [YH] has joined the memo! --\n```");
+    }
+}
+
+#[cfg(test)]
+mod help {
+    use std::fs;
+
+    use super::{TempDir, quirks_dir};
+    use crate::args::Args;
+    use crate::message::{Line, ParsedMessage};
+    use crate::quirk::Characters;
+
+    #[test]
+    fn can_list_commands() {
+        let string = "ARADIA! help";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] can use the following commands --
+ARADIA! join
+ARADIA! leave
+ARADIA! idle
+ARADIA! unidle
+ARADIA! online
+ARADIA! offline
+ARADIA! block userName
+ARADIA! unblock userName
+ARADIA! kick userName
+ARADIA! ban userName
+ARADIA! unban userName
+ARADIA! upload filename
+ARADIA! troll userName
+ARADIA! pester userName
+ARADIA! cease userName
+ARADIA! timeline -H:MM
+ARADIA! help
+ARADIA! profile
+ARADIA! reroll
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn help_includes_custom_commands() {
+        let cs = Characters::from_string_in(&quirks_dir(), "TEREZI! help");
+        let quirked_text = cs.quirked();
+        assert!(quirked_text.contains("\nTEREZI! sniff user\n"));
+    }
+
+    #[test]
+    fn can_parse_global_commands() {
+        let message = ParsedMessage::parse("!list\n\\!list\n!!!\n!list all of them");
+        assert_eq!(message.lines, vec![
+            Line::Global { name: "list".to_string(), args: Args::default(), raw: "!list".to_string() },
+            Line::Escaped("!list".to_string()),
+            Line::Plain("!!!".to_string()),
+            Line::Global { name: "list".to_string(), args: Args::parse("all of them"), raw: "!list all of them".to_string() },
+        ]);
+        assert_eq!(message.to_string(), "!list\n\\!list\n!!!\n!list all of them");
+    }
+
+    #[test]
+    fn can_list_characters() {
+        let cs = Characters::from_string_in(&quirks_dir(), "!list");
+        let quirked_text = cs.quirked();
+        assert!(quirked_text.starts_with("```\n-- Available characters --\n"));
+        assert!(quirked_text.contains("\nARADIA: apocalypseArisen [AA]\n"));
+        assert!(quirked_text.contains("\nTEREZI: gallowsCalibrator [GC]\n"));
+    }
+
+    #[test]
+    fn list_skips_invalid_and_shared_files() {
+        let dir = TempDir::new("list");
+        fs::write(dir.join("ONE.json"), r#"{ "handle": "firstHandle", "acronym": "FH", "quirks": [] }"#).unwrap();
+        fs::write(dir.join("TWO.json"), r#"{ "handle": "secondHandle", "acronym": "SH", "quirks": [] }"#).unwrap();
+        fs::write(dir.join("BROKEN.json"), "{ not json").unwrap();
+        fs::write(dir.join("_commands.json"), "{}").unwrap();
+        fs::write(dir.join("notes.txt"), "not a character").unwrap();
+
+        let cs = Characters::from_string_in(&dir, "!list");
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, "```\n-- Available characters --\nONE: firstHandle [FH]\nTWO: secondHandle [SH]\n```");
+    }
+
+    #[test]
+    fn unknown_global_command_is_unchanged() {
+        let cs = Characters::from_string_in(&quirks_dir(), "!roll 1d20");
+        assert_eq!(cs.quirked(), "!roll 1d20");
+    }
+}
+
+#[cfg(test)]
+mod profile {
+    use super::quirks_dir;
+    use crate::memo::Memo;
+    use crate::quirk::{self, Characters, Context};
+    use crate::settings::Settings;
+
+    #[test]
+    fn can_show_profile() {
+        let string = "ARADIA! profile";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] --
+Name: Aradia Megido
+Pronouns: she/her
+Blood: maroon
+
+A thief of time who likes archeology and ghosts.
+
+AA: the quick brown fox jumped over the lazy dog
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        let quirked_text = cs.quirked();
+        assert_eq!(quirked_text, expected_string);
+    }
+
+    #[test]
+    fn profile_fields_are_optional() {
+        let data = r#"
+        {
+            "handle": "yourHandle",
+            "acronym": "YH",
+            "avatar": "avatars/yourHandle.png",
+            "quirks": [
+                {
+                    "style": "uppercase"
+                }
+            ]
+        }
+        "#;
+        let c = quirk::parse_safe(data.to_string());
+        assert_eq!(c.full_name, None);
+
+        let expected_string = 
+r#"```
+-- yourHandle [YH] --
+Avatar: avatars/yourHandle.png
+
+YH: THE QUICK BROWN FOX JUMPED OVER THE LAZY DOG.
+```"#;
+        let (dir, settings, mut memo) = (quirks_dir(), Settings::default(), Memo::default());
+        let mut ctx = Context::new(&dir, &settings, &mut memo);
+        assert_eq!(c.command_in("profile", &Default::default(), &mut ctx).unwrap(), expected_string);
+    }
+
+    #[test]
+    fn profile_fields_survive_serialization() {
+        let c = Characters::from_string_in(&quirks_dir(), "ARADIA: hi").characters.remove("ARADIA").unwrap();
+        let j = serde_json::to_string_pretty(&c).unwrap();
+        assert!(j.contains("\"blood_color\": \"maroon\""));
+        assert!(!j.contains("avatar"));
+    }
+}
+
+#[cfg(test)]
+mod pester {
+    use std::fs;
+
+    use regex::Regex;
+
+    use super::{TempDir, quirks_dir};
+    use crate::quirk::{Character, Characters};
+    use crate::settings::Settings;
+    use crate::target::Target;
+
+    #[test]
+    fn can_pester() {
+        let c = Character::from_name_in(&quirks_dir(), "ARADIA").unwrap();
+        assert_eq!(c.pester(&Target::derived("carcinoGeneticist", &Settings::default()), Some("16:13")), "```\n-- apocalypseArisen [AA] began pestering carcinoGeneticist [CG] at 16:13 --\n```");
+        assert_eq!(c.pester(&Target::derived("carcinoGeneticist", &Settings::default()), None), "```\n-- apocalypseArisen [AA] began pestering carcinoGeneticist [CG] --\n```");
+    }
+
+    #[test]
+    fn can_cease() {
+        let c = Character::from_name_in(&quirks_dir(), "ARADIA").unwrap();
+        assert_eq!(c.cease(&Target::derived("carcinoGeneticist", &Settings::default()), Some("16:13")), "```\n-- apocalypseArisen [AA] ceased pestering carcinoGeneticist [CG] at 16:13 --\n```");
+        assert_eq!(c.cease(&Target::derived("carcinoGeneticist", &Settings::default()), None), "```\n-- apocalypseArisen [AA] ceased pestering carcinoGeneticist [CG] --\n```");
+    }
+
+    #[test]
+    fn pester_command_uses_current_time() {
+        let cs = Characters::from_string_in(&quirks_dir(), "ARADIA! pester carcinoGeneticist");
+        let re = Regex::new(r"^```\n-- apocalypseArisen \[AA\] began pestering carcinoGeneticist \[CG\] at \d{2}:\d{2} --\n```$").unwrap();
+        assert!(re.is_match(&cs.quirked()));
+    }
+
+    #[test]
+    fn can_configure_timestamp_format() {
+        let dir = TempDir::new("timestamps");
+        fs::write(dir.join("YOU.json"), r#"{ "handle": "yourHandle", "acronym": "YH", "quirks": [] }"#).unwrap();
+
+        fs::write(dir.join("_settings.json"), r#"{ "timestamp_format": null }"#).unwrap();
+        let cs = Characters::from_string_in(&dir, "YOU! cease ectoBiologist");
+        assert_eq!(cs.quirked(), "```\n-- yourHandle [YH] ceased pestering ectoBiologist [EB] --\n```");
+
+        fs::write(dir.join("_settings.json"), r#"{ "timestamp_format": "%Y" }"#).unwrap();
+        let cs = Characters::from_string_in(&dir, "YOU! cease ectoBiologist");
+        let re = Regex::new(r"^```\n-- yourHandle \[YH\] ceased pestering ectoBiologist \[EB\] at \d{4} --\n```$").unwrap();
+        assert!(re.is_match(&cs.quirked()));
+    }
+
+    #[test]
+    fn invalid_timestamp_format_is_left_out() {
+        let settings = crate::settings::Settings { timestamp_format: Some("%Q".to_string()), ..Default::default() };
+        assert_eq!(settings.timestamp(), None);
+    }
+}
+
+#[cfg(test)]
+mod timeline {
+    use super::quirks_dir;
+    use crate::memo::{Memo, Timeline};
+    use crate::quirk::Characters;
+    use test_case::test_case;
+
+    #[test_case("-2:00", -120 ; "past")]
+    #[test_case("+1:30", 90 ; "explicit future")]
+    #[test_case("0:45", 45 ; "implicit future")]
+    #[test_case("-3", -180 ; "hours only")]
+    #[test_case("now", 0 ; "now")]
+    fn can_parse_timeline(s: &str, minutes: i32) {
+        assert_eq!(Timeline::parse(s), Some(Timeline { minutes }));
+    }
+
+    #[test_case("" ; "empty")]
+    #[test_case("soon" ; "words")]
+    #[test_case("-2:5" ; "one digit minutes")]
+    #[test_case("1:60" ; "too many minutes")]
+    #[test_case("--1" ; "double sign")]
+    fn cant_parse_invalid_timeline(s: &str) {
+        assert_eq!(Timeline::parse(s), None);
+    }
+
+    #[test_case(-120, "PAST", "2 HOURS AGO" ; "hours ago")]
+    #[test_case(60, "FUTURE", "1 HOUR FROM NOW" ; "one hour")]
+    #[test_case(-30, "PAST", "30 MINUTES AGO" ; "minutes")]
+    #[test_case(73, "FUTURE", "1:13 HOURS FROM NOW" ; "hours and minutes")]
+    #[test_case(0, "CURRENT", "RIGHT NOW" ; "current")]
+    fn can_describe_timeline(minutes: i32, label: &str, relative: &str) {
+        let t = Timeline { minutes };
+        assert_eq!(t.label(), label);
+        assert_eq!(t.relative(), relative);
+    }
+
+    #[test]
+    fn can_respond_from_timeline() {
+        let string = "ARADIA! timeline +2:00\nARADIA: Hello from the future.";
+        let expected_string = 
+r#"```
+FUTURE apocalypseArisen (FAA) 2 HOURS FROM NOW responded to memo.
+```
+FAA: hello from the future"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        assert_eq!(cs.quirked(), expected_string);
+    }
+
+    #[test]
+    fn timeline_persists_in_memo() {
+        let mut memo = Memo::default();
+        Characters::from_string_in(&quirks_dir(), "ARADIA! timeline -2:00").quirked_in(&mut memo);
+        assert_eq!(memo.timeline("ARADIA"), Some(Timeline { minutes: -120 }));
+
+        let cs = Characters::from_string_in(&quirks_dir(), "ARADIA: Hello.\nKARKAT: Hey.\nARADIA! join");
+        assert_eq!(cs.quirked_in(&mut memo), "PAA: hello\nCG: HEY.\n```\nPAST apocalypseArisen (PAA) 2 HOURS AGO responded to memo.\n```");
+
+        let cs = Characters::from_string_in(&quirks_dir(), "ARADIA! leave\nARADIA: Back to normal.");
+        assert_eq!(cs.quirked_in(&mut memo), "```\nPAST apocalypseArisen (PAA) 2 HOURS AGO ceased responding to memo.\n```\nAA: back to normal");
+        assert_eq!(memo.timeline("ARADIA"), None);
+    }
+
+    #[test]
+    fn invalid_timeline_leaves_line_unchanged() {
+        let string = "ARADIA! timeline yesterday";
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        assert_eq!(cs.quirked(), string);
+    }
+}
+
+#[cfg(test)]
+mod who {
+    use super::quirks_dir;
+    use crate::memo::Memo;
+    use crate::quirk::Characters;
+
+    fn quirked_in(string: &str, memo: &mut Memo) -> String {
+        Characters::from_string_in(&quirks_dir(), string).quirked_in(memo)
+    }
+
+    #[test]
+    fn can_track_participants() {
+        let mut memo = Memo::default();
+        quirked_in("ARADIA! join\nKARKAT! join\nTEREZI! timeline -1:30", &mut memo);
+
+        let expected_string = 
+r#"```
+-- Responding to the memo --
+apocalypseArisen [AA]
+carcinoGeneticist [CG]
+PAST gallowsCalibrator (PGC) 1:30 HOURS AGO
+```"#;
+        assert_eq!(quirked_in("!who", &mut memo), expected_string);
+
+        quirked_in("KARKAT! leave\nARADIA! kick gallowsCalibrator", &mut memo);
+        assert_eq!(quirked_in("!who", &mut memo), "```\n-- Responding to the memo --\napocalypseArisen [AA]\n```");
+        assert_eq!(memo.timeline("TEREZI"), None);
+    }
+
+    #[test]
+    fn empty_memo_has_nobody() {
+        assert_eq!(quirked_in("!who", &mut Memo::default()), "```\n-- Nobody is responding to the memo --\n```");
+    }
+
+    #[test]
+    fn banned_characters_cannot_rejoin() {
+        let mut memo = Memo::default();
+        quirked_in("KARKAT! join\nARADIA! join\nARADIA! ban carcinoGeneticist", &mut memo);
+        assert!(!memo.is_participant("carcinoGeneticist"));
+
+        assert_eq!(quirked_in("KARKAT! join", &mut memo), "```\n-- carcinoGeneticist [CG] is banned from the memo! --\n```");
+        assert_eq!(quirked_in("KARKAT! timeline +1:00", &mut memo), "```\n-- carcinoGeneticist [CG] is banned from the memo! --\n```");
+        assert!(!memo.is_participant("carcinoGeneticist"));
+        assert_eq!(memo.timeline("KARKAT"), None);
+
+        quirked_in("ARADIA! unban carcinoGeneticist", &mut memo);
+        assert_eq!(quirked_in("KARKAT! join", &mut memo), "```\n-- carcinoGeneticist [CG] has joined the memo! --\n```");
+        assert!(memo.is_participant("carcinoGeneticist"));
+    }
+
+    #[test]
+    fn joining_twice_does_not_duplicate() {
+        let mut memo = Memo::default();
+        quirked_in("ARADIA! join\nARADIA! join", &mut memo);
+        assert_eq!(memo.participants.len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod targets {
+    use super::quirks_dir;
+    use crate::memo::Memo;
+    use crate::quirk::Characters;
+    use crate::settings::Settings;
+    use crate::target::Target;
+    use test_case::test_case;
+
+    #[test_case("KARKAT" ; "file name")]
+    #[test_case("karkat" ; "file name in lowercase")]
+    #[test_case("carcinoGeneticist" ; "handle")]
+    #[test_case("CARCINOGENETICIST" ; "handle in uppercase")]
+    #[test_case("Karkat Vantas" ; "alias")]
+    #[test_case("kk" ; "alias in lowercase")]
+    fn can_resolve_known_character(user: &str) {
+        assert_eq!(Target::resolve(&quirks_dir(), user, &Settings::default()), Target { handle: "carcinoGeneticist".to_string(), acronym: "CG".to_string() });
+    }
+
+    #[test_case("ectoBiologist", "EB" ; "camel case")]
+    #[test_case("../karkat", "K" ; "path")]
+    fn unknown_names_are_derived(user: &str, acronym: &str) {
+        assert_eq!(Target::resolve(&quirks_dir(), user, &Settings::default()), Target { handle: user.to_string(), acronym: acronym.to_string() });
+    }
+
+    #[test]
+    fn can_kick_known_character() {
+        let string = "ARADIA! kick KARKAT";
+        let expected_string = 
+r#"```
+-- apocalypseArisen [AA] has kicked carcinoGeneticist [CG] from the memo! --
+```"#;
+
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        assert_eq!(cs.quirked(), expected_string);
+    }
+
+    #[test]
+    fn banning_by_name_bans_the_handle() {
+        let mut memo = Memo::default();
+        Characters::from_string_in(&quirks_dir(), "ARADIA! ban Karkat Vantas").quirked_in(&mut memo);
+        assert!(memo.is_banned("carcinoGeneticist"));
+
+        let cs = Characters::from_string_in(&quirks_dir(), "KARKAT! join");
+        assert_eq!(cs.quirked_in(&mut memo), "```\n-- carcinoGeneticist [CG] is banned from the memo! --\n```");
+    }
+
+    #[test]
+    fn custom_commands_resolve_targets() {
+        let cs = Characters::from_string_in(&quirks_dir(), "TEREZI! sniff KARKAT");
+        assert_eq!(cs.quirked(), "```\n-- gallowsCalibrator [GC] sniffs out carcinoGeneticist [CG]! --\n```");
+    }
+}
+
+#[cfg(test)]
+mod acronyms {
+    use std::fs;
+
+    use super::TempDir;
+    use crate::acronym::derive_acronym;
+    use crate::quirk::{Character, Characters};
+    use crate::settings::Settings;
+    use crate::target::Target;
+    use test_case::test_case;
+
+    #[test_case("ectoBiologist", "EB" ; "camel case")]
+    #[test_case("ecto biologist", "EB" ; "spaces")]
+    #[test_case("turntech_godhead", "TG" ; "underscores")]
+    #[test_case("KARKAT", "K" ; "all capitals")]
+    #[test_case("HTMLParser", "HP" ; "run of capitals")]
+    #[test_case("ghostyTrickster2", "GT" ; "trailing digits")]
+    #[test_case("trickster2Mode", "TM" ; "digits before a capital")]
+    #[test_case("élanVital", "ÉV" ; "non ascii")]
+    #[test_case("", "" ; "empty")]
+    #[test_case("--", "" ; "no letters")]
+    fn can_derive_acronym(handle: &str, acronym: &str) {
+        assert_eq!(derive_acronym(handle, None), acronym);
+    }
+
+    #[test]
+    fn max_length_limits_acronym() {
+        assert_eq!(derive_acronym("Some Really Long Display Name", Some(2)), "SR");
+        assert_eq!(derive_acronym("Some Really Long Display Name", None), "SRLDN");
+    }
+
+    #[test]
+    fn targets_use_max_length_from_settings() {
+        let settings = Settings { acronym_max_length: Some(3), ..Settings::default() };
+        assert_eq!(Target::derived("Some Really Long Display Name", &settings).acronym, "SRL");
+    }
+
+    #[test]
+    fn missing_acronym_is_derived_from_handle() {
+        let dir = TempDir::new("acronyms");
+        fs::write(dir.join("JOHN.json"), "{ \"handle\": \"ectoBiologist\", \"quirks\": [] }").unwrap();
+        fs::write(dir.join("_settings.json"), "{ \"acronym_max_length\": null }").unwrap();
+
+        let c = Character::from_name_in(&dir, "JOHN").unwrap();
+        assert_eq!(c.acronym, "EB");
+        assert_eq!(Character::all_in(&dir)[0].acronym, "EB");
+        assert_eq!(Characters::from_string_in(&dir, "JOHN! online").quirked(), "```\n-- ectoBiologist [EB] is now online! --\n```");
+    }
+}
+
+#[cfg(test)]
+mod presence {
+    use super::quirks_dir;
+    use crate::presence::{Presence, PresenceChange};
+    use crate::quirk::Characters;
+    use test_case::test_case;
+
+    #[test_case("ARADIA! online", Presence::Online ; "online")]
+    #[test_case("ARADIA! unidle", Presence::Online ; "unidle")]
+    #[test_case("ARADIA! idle", Presence::Idle ; "idle")]
+    fn presence_commands_set_status(string: &str, presence: Presence) {
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        assert_eq!(cs.presence(), Some(PresenceChange { presence, status: Some("communing with the dead".to_string()) }));
+    }
+
+    #[test]
+    fn offline_clears_status_text() {
+        let cs = Characters::from_string_in(&quirks_dir(), "ARADIA! offline");
+        assert_eq!(cs.presence(), Some(PresenceChange { presence: Presence::Offline, status: None }));
+    }
+
+    #[test]
+    fn last_presence_command_wins() {
+        let cs = Characters::from_string_in(&quirks_dir(), "ARADIA! online\nKARKAT! idle");
+        assert_eq!(cs.presence(), Some(PresenceChange { presence: Presence::Idle, status: None }));
+    }
+
+    #[test_case("ARADIA: hello" ; "dialogue")]
+    #[test_case("ARADIA! join" ; "other command")]
+    #[test_case("NOBODY! online" ; "unknown character")]
+    fn other_lines_leave_presence_alone(string: &str) {
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        assert_eq!(cs.presence(), None);
+    }
+}
+
+#[cfg(test)]
+mod speaker {
+    use super::quirks_dir;
+    use crate::quirk::Characters;
+    use test_case::test_case;
+
+    #[test_case("ARADIA: hello", Some("apocalypseArisen") ; "dialogue")]
+    #[test_case("ARADIA: hello\nKARKAT! join", Some("carcinoGeneticist") ; "last character wins")]
+    #[test_case("KARKAT: hi\nNOBODY: hello", Some("carcinoGeneticist") ; "unknown characters are skipped")]
+    #[test_case("just talking", None ; "no character")]
+    fn can_find_speaker(string: &str, handle: Option<&str>) {
+        let cs = Characters::from_string_in(&quirks_dir(), string);
+        assert_eq!(cs.speaker().map(|c| c.handle.as_str()), handle);
+    }
+}
+
+#[cfg(test)]
+mod settings {
+    use std::fs;
+
+    use super::TempDir;
+    use crate::quirk::{Character, Characters};
+    use crate::settings::{Delivery, ReactionAction, Settings, load_settings};
+    use test_case::test_case;
+
+    #[test]
+    fn broken_files_are_ignored() {
+        let dir = TempDir::new("broken-settings");
+        fs::write(dir.join("_settings.json"), "{ \"delivery\": \"carrier pigeon\" }").unwrap();
+        fs::write(dir.join("YOU.json"), r#"{ "handle": "yourHandle", "quirks": [] }"#).unwrap();
+        fs::write(dir.join("BROKEN.json"), "{ not json").unwrap();
+
+        assert_eq!(load_settings(&dir), Settings::default());
+        assert_eq!(Character::from_name_in(&dir, "YOU").unwrap().acronym, "YH");
+        assert!(Character::from_name_in(&dir, "BROKEN").is_none());
+        assert_eq!(Characters::from_string_in(&dir, "BROKEN: hi\nYOU: hi").quirked(), "BROKEN: hi\nYH: hi");
+    }
+
+    #[test_case("{}", Delivery::Edit ; "default")]
+    #[test_case("{ \"delivery\": \"edit\" }", Delivery::Edit ; "edit")]
+    #[test_case("{ \"delivery\": \"resend\" }", Delivery::Resend ; "resend")]
+    #[test_case("{ \"delivery\": \"webhook\" }", Delivery::Webhook ; "webhook")]
+    fn can_choose_delivery(json: &str, delivery: Delivery) {
+        let settings: Settings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.delivery, delivery);
+    }
+
+    #[test]
+    fn can_map_reactions() {
+        let json = r#"{ "reactions": { "↩️": "undo", "🎲": "reroll", "🗑️": "delete", "kk": { "quirk": "KARKAT" } } }"#;
+        let settings: Settings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.reactions.len(), 4);
+        assert_eq!(settings.reactions["↩️"], ReactionAction::Undo);
+        assert_eq!(settings.reactions["🎲"], ReactionAction::Reroll);
+        assert_eq!(settings.reactions["🗑️"], ReactionAction::Delete);
+        assert_eq!(settings.reactions["kk"], ReactionAction::Quirk("KARKAT".to_string()));
+        assert!(Settings::default().reactions.is_empty());
+    }
+}
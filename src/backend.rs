@@ -0,0 +1,131 @@
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use discord::model::{ChannelId, MessageId, ServerId, UserId};
+use rustblood_core::{Character, PresenceChange};
+
+/// A chat message, with only the parts the bot looks at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatMessage {
+	pub id: MessageId,
+	pub channel_id: ChannelId,
+	pub author: UserId,
+	pub content: String,
+	/// Users mentioned in the message, with their usernames.
+	pub mentions: Vec<(UserId, String)>,
+	/// The message this one replies to.
+	pub reference: Option<MessageId>,
+}
+
+/// An emoji someone reacted to a message with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatReaction {
+	pub channel_id: ChannelId,
+	pub message_id: MessageId,
+	pub user: UserId,
+	/// The emoji itself, or the name of a custom emoji.
+	pub emoji: String,
+	/// The ID of a custom emoji.
+	pub emoji_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatEvent {
+	MessageCreate(ChatMessage),
+	/// A message's text was changed.
+	MessageUpdate(ChatMessage),
+	ReactionAdd(ChatReaction),
+	/// Anything the bot doesn't act on.
+	Other,
+}
+
+impl ChatEvent {
+	/// The channel the event happened in.
+	pub fn channel(&self) -> Option<ChannelId> {
+		match self {
+			ChatEvent::MessageCreate(message) | ChatEvent::MessageUpdate(message) => Some(message.channel_id),
+			ChatEvent::ReactionAdd(reaction) => Some(reaction.channel_id),
+			ChatEvent::Other => None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatError {
+	/// The connection was closed, with the close code if there was one.
+	Closed(Option<u16>, String),
+	/// Too many requests were made, and the next one should wait this long.
+	RateLimited(Duration),
+	/// The message, channel or user asked for doesn't exist.
+	NotFound,
+	/// The token was rejected, so nothing will work until it's replaced.
+	Unauthorized,
+	/// Any other failure, such as an HTTP request that didn't go through.
+	Other(String),
+}
+
+impl fmt::Display for ChatError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ChatError::Closed(code, body) => write!(f, "connection closed with code {:?}: {}", code, body),
+			ChatError::RateLimited(retry_after) => write!(f, "rate limited for {:.1}s", retry_after.as_secs_f64()),
+			ChatError::NotFound => write!(f, "not found"),
+			ChatError::Unauthorized => write!(f, "unauthorized, check DISCORD_TOKEN"),
+			ChatError::Other(err) => write!(f, "{}", err),
+		}
+	}
+}
+
+impl ChatError {
+	/// Whether reconnecting can't help, such as when the token is invalid.
+	pub fn is_fatal(&self) -> bool {
+		// https://discord.com/developers/docs/topics/opcodes-and-status-codes#gateway-gateway-close-event-codes
+		matches!(self, ChatError::Unauthorized | ChatError::Closed(Some(4004 | 4010 | 4011 | 4012 | 4013 | 4014), _))
+	}
+}
+
+pub type ChatResult<T> = Result<T, ChatError>;
+
+/// Everything the bot needs from a chat service, so the event loop can run against
+/// Discord or, in tests, an in-memory stand-in.
+pub trait ChatBackend {
+	/// The account the bot runs as.
+	fn current_user(&self) -> UserId;
+	/// Waits for the next event.
+	fn recv_event(&mut self) -> ChatResult<ChatEvent>;
+	/// Opens a new connection after the last one was closed.
+	fn reconnect(&mut self) -> ChatResult<()>;
+	/// A handle for sending and changing messages from other threads.
+	fn messages(&self) -> Arc<dyn MessageApi>;
+
+	fn set_presence(&mut self, change: PresenceChange);
+}
+
+/// The part of a chat service that sends and changes messages and looks up
+/// servers, which the worker threads share.
+pub trait MessageApi: Send + Sync {
+	fn edit_message(&self, channel: ChannelId, message: MessageId, content: &str) -> ChatResult<()>;
+	fn delete_message(&self, channel: ChannelId, message: MessageId) -> ChatResult<()>;
+	/// Sends `content` as a new message in place of `message`, keeping what it replied
+	/// to and its attachments. The original is left for the caller to delete. Returns
+	/// the new message's ID.
+	fn resend_message(&self, channel: ChannelId, message: MessageId, content: &str) -> ChatResult<MessageId>;
+	/// Posts `content` under a character's handle and avatar in place of `message`,
+	/// keeping its attachments. Avatar files are relative to the quirks folder `dir`.
+	/// The original is left for the caller to delete. Returns the new message's ID.
+	fn send_as(&self, channel: ChannelId, message: MessageId, c: &Character, dir: &Path, content: &str) -> ChatResult<MessageId>;
+	/// Changes the text of a message posted with `send_as`.
+	fn edit_webhook_message(&self, channel: ChannelId, message: MessageId, content: &str) -> ChatResult<()>;
+	/// Deletes a message posted with `send_as`.
+	fn delete_webhook_message(&self, channel: ChannelId, message: MessageId) -> ChatResult<()>;
+	/// Takes back one of our own reactions.
+	fn remove_reaction(&self, reaction: &ChatReaction) -> ChatResult<()>;
+	/// The server a channel belongs to, or `None` for direct messages.
+	fn server_of(&self, channel: ChannelId) -> Option<ServerId>;
+	/// A user's nickname in a server, or `None` if they don't have one.
+	fn nickname(&self, server: ServerId, user: UserId) -> ChatResult<Option<String>>;
+	/// Changes our own nickname in a server. An empty nickname clears it.
+	fn set_nickname(&self, server: ServerId, nickname: &str) -> ChatResult<()>;
+}
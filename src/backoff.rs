@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+/// Exponentially growing delays between reconnect attempts, with random jitter so
+/// that many clients dropped at once don't all come back at the same moment.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+	pub base: Duration,
+	pub max: Duration,
+	attempt: u32,
+}
+
+impl Backoff {
+	pub fn new(base: Duration, max: Duration) -> Backoff {
+		Backoff { base, max, attempt: 0 }
+	}
+
+	/// How many delays have been handed out since the last reset.
+	pub fn attempts(&self) -> u32 {
+		self.attempt
+	}
+
+	/// The delay before the next attempt.
+	pub fn next_delay(&mut self) -> Duration {
+		let delay = self.delay(rand::thread_rng().gen());
+		self.attempt = self.attempt.saturating_add(1);
+		delay
+	}
+
+	/// The delay for the current attempt given a random number in `0..1`: at least
+	/// half of `base * 2^attempt`, capped at `max`, with the rest left to chance.
+	pub fn delay(&self, random: f64) -> Duration {
+		let ceiling = self.base.saturating_mul(2u32.saturating_pow(self.attempt)).min(self.max);
+		ceiling / 2 + (ceiling / 2).mul_f64(random.clamp(0.0, 1.0))
+	}
+
+	pub fn reset(&mut self) {
+		self.attempt = 0;
+	}
+}
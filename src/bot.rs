@@ -0,0 +1,168 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use rustblood_core::{Characters, Line, ParsedMessage, Presence, PresenceChange};
+
+use crate::backend::{ChatBackend, ChatError, ChatEvent, MessageApi};
+use crate::backoff::Backoff;
+use crate::edits::EditQueue;
+use crate::handler::{Handler, State};
+use crate::workers::Workers;
+
+/// Receives events and hands them to the workers, which do the quirking.
+pub struct Bot {
+	/// The quirks folder characters and settings are loaded from.
+	pub dir: PathBuf,
+	/// Delays between attempts to reconnect.
+	pub backoff: Backoff,
+	/// How each worker retries edits.
+	pub edits: EditQueue,
+	/// How many threads handle events.
+	pub workers: usize,
+	/// What the bot remembers between events, such as memos and the messages it edited.
+	pub state: Arc<State>,
+	pool: Arc<Mutex<Option<Workers>>>,
+	stopped: Arc<AtomicBool>,
+}
+
+impl Bot {
+	pub fn new(dir: PathBuf) -> Bot {
+		Bot {
+			dir,
+			backoff: Backoff::new(Duration::from_secs(1), Duration::from_secs(300)),
+			edits: EditQueue::default(),
+			workers: 4,
+			state: Arc::new(State::default()),
+			pool: Arc::default(),
+			stopped: Arc::default(),
+		}
+	}
+
+	/// Handles events, reconnecting whenever the connection drops, until it's closed
+	/// for good. Then finishes sending messages and puts back anything the bot
+	/// changed about the account.
+	pub fn run(&mut self, backend: &mut impl ChatBackend) -> ChatError {
+		let fatal = loop {
+			match backend.recv_event() {
+				Ok(event) => self.handle(backend, event),
+				Err(err) if err.is_fatal() => break err,
+				Err(err @ ChatError::Closed(..)) => {
+					println!("Gateway closed on us, {}", err);
+					if let Err(err) = self.reconnect(backend) {
+						break err;
+					}
+				},
+				Err(err) => println!("Error receiving event: {}", err),
+			}
+		};
+		self.shutdown(backend).run();
+		fatal
+	}
+
+	/// Tries to reconnect until it works, waiting longer after each failure.
+	/// Returns the error if reconnecting can't work.
+	fn reconnect(&mut self, backend: &mut impl ChatBackend) -> Result<(), ChatError> {
+		loop {
+			let delay = self.backoff.next_delay();
+			println!("Reconnecting in {:.1}s (attempt {})", delay.as_secs_f64(), self.backoff.attempts());
+			thread::sleep(delay);
+			match backend.reconnect() {
+				Ok(()) => {
+					println!("Reconnected");
+					self.backoff.reset();
+					return Ok(());
+				},
+				Err(err) if err.is_fatal() => return Err(err),
+				Err(err) => println!("Couldn't reconnect: {}", err),
+			}
+		}
+	}
+
+	/// Hands an event to the worker for its channel, and makes any presence change it
+	/// asks for straight away. Events are ignored once the bot has shut down.
+	pub fn handle(&mut self, backend: &mut impl ChatBackend, event: ChatEvent) {
+		let presence = self.presence(backend, &event);
+		// Checked under the lock, so a pool can't be started after shutting down took it.
+		let mut pool = self.pool.lock().unwrap_or_else(|e| e.into_inner());
+		if self.stopped.load(Ordering::SeqCst) {
+			return;
+		}
+		pool.get_or_insert_with(|| {
+			let handler = Handler {
+				dir: self.dir.clone(),
+				user: backend.current_user(),
+				api: backend.messages(),
+				state: self.state.clone(),
+			};
+			Workers::new(self.workers, Arc::new(handler), &self.edits)
+		}).submit(event);
+		drop(pool);
+		if let Some(change) = presence {
+			backend.set_presence(change);
+		}
+	}
+
+	/// The presence change one of our messages asks for, if syncing presence is on.
+	/// Only the event loop holds the connection, so this is worked out here rather
+	/// than on the workers. Characters are only loaded for messages with a presence
+	/// command in them.
+	fn presence(&self, backend: &impl ChatBackend, event: &ChatEvent) -> Option<PresenceChange> {
+		let message = match event {
+			ChatEvent::MessageCreate(message) | ChatEvent::MessageUpdate(message) if message.author == backend.current_user() => message,
+			_ => return None,
+		};
+		let parsed = ParsedMessage::parse(&message.content);
+		let asked = parsed.lines.iter().any(|line| matches!(line, Line::Command { name, .. } if Presence::from_command(name).is_some()));
+		if !asked {
+			return None;
+		}
+		let cs = Characters::from_message_in(&self.dir, parsed);
+		if !cs.settings.sync_presence {
+			return None;
+		}
+		cs.presence()
+	}
+
+	/// Waits for the workers to finish every event handed to them so far.
+	#[cfg(test)]
+	pub fn flush(&mut self) {
+		if let Some(pool) = self.pool.lock().unwrap_or_else(|e| e.into_inner()).take() {
+			pool.join();
+		}
+	}
+
+	/// A handle for stopping the bot from another thread, such as when the process
+	/// is asked to exit.
+	pub fn shutdown(&self, backend: &impl ChatBackend) -> Shutdown {
+		Shutdown {
+			pool: self.pool.clone(),
+			stopped: self.stopped.clone(),
+			state: self.state.clone(),
+			api: backend.messages(),
+		}
+	}
+}
+
+/// Stops a bot from another thread.
+#[derive(Clone)]
+pub struct Shutdown {
+	pool: Arc<Mutex<Option<Workers>>>,
+	stopped: Arc<AtomicBool>,
+	state: Arc<State>,
+	api: Arc<dyn MessageApi>,
+}
+
+impl Shutdown {
+	/// Finishes sending messages and puts back the nicknames the bot changed. Any
+	/// events after this are ignored.
+	pub fn run(&self) {
+		self.stopped.store(true, Ordering::SeqCst);
+		if let Some(pool) = self.pool.lock().unwrap_or_else(|e| e.into_inner()).take() {
+			pool.join();
+		}
+		self.state.nicknames.lock().unwrap_or_else(|e| e.into_inner()).restore_all(&*self.api);
+	}
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use discord::model::{Channel, ChannelId, EmojiId, Event, Game, Message, MessageId, OnlineStatus, ReactionEmoji, ServerId, UserId};
+use discord::{Connection, Discord};
+use rustblood_core::{Character, Presence, PresenceChange};
+
+use crate::backend::{ChatBackend, ChatError, ChatEvent, ChatMessage, ChatReaction, ChatResult, MessageApi};
+use crate::resend::{attachments, resend};
+use crate::rest::Rest;
+use crate::webhooks::Webhooks;
+
+/// Runs the bot on a Discord account through discord-rs, with plain HTTP requests
+/// for the few things it doesn't support.
+pub struct DiscordBackend {
+	discord: Arc<Discord>,
+	connection: Connection,
+	user: UserId,
+	messages: Arc<DiscordMessages>,
+}
+
+impl DiscordBackend {
+	/// Logs in with a user token and opens the gateway connection.
+	pub fn connect(token: &str) -> ChatResult<DiscordBackend> {
+		let discord = Arc::new(Discord::from_user_token(token).map_err(chat_error)?);
+		let (connection, _) = discord.connect().map_err(chat_error)?;
+		let user = discord.get_current_user().map_err(chat_error)?.id;
+		let rest = Rest::new(token);
+		let messages = Arc::new(DiscordMessages {
+			discord: discord.clone(),
+			webhooks: Webhooks::new(rest.clone()),
+			rest,
+			servers: Mutex::new(HashMap::new()),
+		});
+		Ok(DiscordBackend {
+			discord,
+			connection,
+			user,
+			messages,
+		})
+	}
+}
+
+impl ChatBackend for DiscordBackend {
+	fn current_user(&self) -> UserId {
+		self.user
+	}
+
+	fn recv_event(&mut self) -> ChatResult<ChatEvent> {
+		match self.connection.recv_event() {
+			Ok(Event::MessageCreate(message)) => Ok(ChatEvent::MessageCreate(chat_message(message))),
+			// Updates without text or an author are embeds being filled in, not edits.
+			Ok(Event::MessageUpdate { id, channel_id, content: Some(content), author: Some(author), mentions, .. }) => {
+				Ok(ChatEvent::MessageUpdate(ChatMessage {
+					id,
+					channel_id,
+					author: author.id,
+					content,
+					mentions: mentions.unwrap_or_default().into_iter().map(|u| (u.id, u.name)).collect(),
+					reference: None,
+				}))
+			},
+			Ok(Event::ReactionAdd(reaction)) => {
+				let (emoji, emoji_id) = match reaction.emoji {
+					ReactionEmoji::Unicode(emoji) => (emoji, None),
+					ReactionEmoji::Custom { name, id } => (name, Some(id.0)),
+				};
+				Ok(ChatEvent::ReactionAdd(ChatReaction {
+					channel_id: reaction.channel_id,
+					message_id: reaction.message_id,
+					user: reaction.user_id,
+					emoji,
+					emoji_id,
+				}))
+			},
+			Ok(_) => Ok(ChatEvent::Other),
+			Err(err) => Err(chat_error(err)),
+		}
+	}
+
+	fn reconnect(&mut self) -> ChatResult<()> {
+		let (connection, _) = self.discord.connect().map_err(chat_error)?;
+		self.connection = connection;
+		Ok(())
+	}
+
+	fn messages(&self) -> Arc<dyn MessageApi> {
+		self.messages.clone()
+	}
+
+	fn set_presence(&mut self, change: PresenceChange) {
+		let (status, afk) = match change.presence {
+			Presence::Online => (OnlineStatus::Online, false),
+			Presence::Idle => (OnlineStatus::Idle, true),
+			Presence::Offline => (OnlineStatus::Invisible, false),
+		};
+		// discord-rs has no custom status activity, so the status text shows as "Playing …".
+		self.connection.set_presence(change.status.map(Game::playing), status, afk);
+	}
+}
+
+/// Sends and changes messages on Discord. Shared between the worker threads.
+pub struct DiscordMessages {
+	discord: Arc<Discord>,
+	rest: Rest,
+	webhooks: Webhooks,
+	servers: Mutex<HashMap<ChannelId, Option<ServerId>>>,
+}
+
+impl MessageApi for DiscordMessages {
+	fn edit_message(&self, channel: ChannelId, message: MessageId, content: &str) -> ChatResult<()> {
+		self.discord.edit_message(channel, message, content).map(|_| ()).map_err(chat_error)
+	}
+
+	fn delete_message(&self, channel: ChannelId, message: MessageId) -> ChatResult<()> {
+		self.discord.delete_message(channel, message).map_err(chat_error)
+	}
+
+	fn resend_message(&self, channel: ChannelId, message: MessageId, content: &str) -> ChatResult<MessageId> {
+		resend(&self.rest, channel, message, content)
+	}
+
+	fn send_as(&self, channel: ChannelId, message: MessageId, c: &Character, dir: &Path, content: &str) -> ChatResult<MessageId> {
+		let original = self.rest.get(&format!("/channels/{}/messages/{}", channel.0, message.0))?;
+		let files = attachments(&self.rest, &original);
+		self.webhooks.send(channel, c, dir, content, files)
+	}
+
+	fn edit_webhook_message(&self, channel: ChannelId, message: MessageId, content: &str) -> ChatResult<()> {
+		self.webhooks.edit(channel, message, content)
+	}
+
+	fn delete_webhook_message(&self, channel: ChannelId, message: MessageId) -> ChatResult<()> {
+		self.webhooks.delete(channel, message)
+	}
+
+	fn remove_reaction(&self, reaction: &ChatReaction) -> ChatResult<()> {
+		let emoji = match reaction.emoji_id {
+			Some(id) => ReactionEmoji::Custom { name: reaction.emoji.clone(), id: EmojiId(id) },
+			None => ReactionEmoji::Unicode(reaction.emoji.clone()),
+		};
+		self.discord.delete_reaction(reaction.channel_id, reaction.message_id, None, emoji).map_err(chat_error)
+	}
+
+	fn server_of(&self, channel: ChannelId) -> Option<ServerId> {
+		if let Some(server) = self.servers.lock().unwrap_or_else(|e| e.into_inner()).get(&channel) {
+			return *server;
+		}
+		// Failed lookups aren't remembered, so they're tried again next time.
+		let server = match self.discord.get_channel(channel) {
+			Ok(Channel::Public(channel)) => Some(channel.server_id),
+			Ok(_) => None,
+			Err(_) => return None,
+		};
+		self.servers.lock().unwrap_or_else(|e| e.into_inner()).insert(channel, server);
+		server
+	}
+
+	fn nickname(&self, server: ServerId, user: UserId) -> ChatResult<Option<String>> {
+		self.discord.get_member(server, user).map(|m| m.nick).map_err(chat_error)
+	}
+
+	fn set_nickname(&self, server: ServerId, nickname: &str) -> ChatResult<()> {
+		self.discord.edit_nickname(server, nickname).map_err(chat_error)
+	}
+}
+
+fn chat_message(message: Message) -> ChatMessage {
+	ChatMessage {
+		id: message.id,
+		channel_id: message.channel_id,
+		author: message.author.id,
+		content: message.content,
+		mentions: message.mentions.into_iter().map(|u| (u.id, u.name)).collect(),
+		reference: message.message_reference.and_then(|r| r.message_id),
+	}
+}
+
+fn chat_error(err: discord::Error) -> ChatError {
+	match err {
+		discord::Error::Closed(code, body) => ChatError::Closed(code, body),
+		discord::Error::RateLimited(retry_after) => ChatError::RateLimited(Duration::from_millis(retry_after)),
+		discord::Error::Status(status, _) if status.to_u16() == 401 => ChatError::Unauthorized,
+		discord::Error::Status(status, _) if status.to_u16() == 404 => ChatError::NotFound,
+		err => ChatError::Other(err.to_string()),
+	}
+}
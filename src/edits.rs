@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use discord::model::{ChannelId, MessageId};
+
+use crate::backend::{ChatError, MessageApi};
+
+#[derive(Clone)]
+struct Edit {
+	channel: ChannelId,
+	message: MessageId,
+	content: String,
+	/// Posted through a webhook, so only the webhook can edit it.
+	webhook: bool,
+	attempts: u32,
+	ready_at: Instant,
+}
+
+/// Edits waiting to be made, sent in the order they were queued. Rate limits hold
+/// the queue back until they pass, failed edits are retried a few times, and edits
+/// to messages which no longer exist are dropped.
+#[derive(Clone)]
+pub struct EditQueue {
+	pending: VecDeque<Edit>,
+	/// Tries an edit gets before it's given up on.
+	pub max_attempts: u32,
+	/// Wait after the first failure, doubled after each one after that.
+	pub retry_delay: Duration,
+	/// Longest the queue sleeps for while flushing. Edits that aren't ready by then
+	/// are left for the next flush.
+	pub max_wait: Duration,
+}
+
+impl Default for EditQueue {
+	fn default() -> EditQueue {
+		EditQueue {
+			pending: VecDeque::new(),
+			max_attempts: 4,
+			retry_delay: Duration::from_secs(1),
+			max_wait: Duration::from_secs(5),
+		}
+	}
+}
+
+impl EditQueue {
+	#[cfg(test)]
+	pub fn len(&self) -> usize {
+		self.pending.len()
+	}
+
+	#[cfg(test)]
+	pub fn is_empty(&self) -> bool {
+		self.pending.is_empty()
+	}
+
+	/// Queues an edit. One still waiting for the same message is replaced, since only
+	/// the newest content matters.
+	pub fn push(&mut self, channel: ChannelId, message: MessageId, content: &str) {
+		self.queue(channel, message, content, false);
+	}
+
+	/// Queues an edit to a message posted through a webhook.
+	pub fn push_webhook(&mut self, channel: ChannelId, message: MessageId, content: &str) {
+		self.queue(channel, message, content, true);
+	}
+
+	fn queue(&mut self, channel: ChannelId, message: MessageId, content: &str, webhook: bool) {
+		if let Some(edit) = self.pending.iter_mut().find(|e| e.message == message) {
+			edit.content = content.to_string();
+			return;
+		}
+		self.pending.push_back(Edit {
+			channel,
+			message,
+			content: content.to_string(),
+			webhook,
+			attempts: 0,
+			ready_at: Instant::now(),
+		});
+	}
+
+	/// When the next edit is ready to be made, if there is one.
+	pub fn next_ready(&self) -> Option<Instant> {
+		self.pending.front().map(|e| e.ready_at)
+	}
+
+	/// Makes every edit that's ready or will be within `max_wait`.
+	pub fn flush(&mut self, api: &dyn MessageApi) {
+		while let Some(edit) = self.pending.front_mut() {
+			let wait = edit.ready_at.saturating_duration_since(Instant::now());
+			if wait > self.max_wait {
+				return;
+			}
+			thread::sleep(wait);
+
+			let edited = if edit.webhook {
+				api.edit_webhook_message(edit.channel, edit.message, &edit.content)
+			} else {
+				api.edit_message(edit.channel, edit.message, &edit.content)
+			};
+			match edited {
+				Ok(()) => {},
+				Err(ChatError::NotFound) => println!("Dropping edit to message {}, which no longer exists", edit.message.0),
+				Err(ChatError::RateLimited(retry_after)) => {
+					println!("Rate limited, holding edits back for {:.1}s", retry_after.as_secs_f64());
+					edit.ready_at = Instant::now() + retry_after;
+					continue;
+				},
+				Err(err) => {
+					edit.attempts += 1;
+					if edit.attempts < self.max_attempts {
+						println!("Couldn't edit message {}, trying again: {}", edit.message.0, err);
+						edit.ready_at = Instant::now() + self.retry_delay * 2u32.pow(edit.attempts - 1);
+						continue;
+					}
+					println!("Giving up on editing message {}: {}", edit.message.0, err);
+				},
+			}
+			self.pending.pop_front();
+		}
+	}
+}
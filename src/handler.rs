@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use discord::model::{ChannelId, MessageId, UserId};
+use rustblood_core::settings::load_settings;
+use rustblood_core::{Character, Characters, Delivery, Line, Memo, ParsedMessage, Presence, ReactionAction};
+
+use crate::backend::{ChatEvent, ChatMessage, ChatReaction, MessageApi};
+use crate::edits::EditQueue;
+use crate::history::{Entry, History};
+use crate::mentions::Mentions;
+use crate::nicknames::Nicknames;
+
+/// What the bot remembers between events, shared by the workers. Apart from
+/// nicknames, which are changed one at a time, nothing stays locked during a request.
+#[derive(Default)]
+pub struct State {
+	/// Memo state such as timelines is kept per channel for as long as we're running.
+	pub memos: Mutex<HashMap<ChannelId, Memo>>,
+	/// Messages the bot has edited, so it can tell its own edits from ours.
+	pub history: Mutex<History>,
+	pub mentions: Mentions,
+	pub nicknames: Mutex<Nicknames>,
+}
+
+/// How a quirked message takes the place of the original.
+pub enum Via {
+	Edit,
+	/// Edits a message the bot already posted through a webhook.
+	EditWebhook,
+	Resend,
+	/// Through a webhook as the character, with avatar files relative to the quirks folder.
+	Webhook(Box<Character>, PathBuf),
+}
+
+/// A quirked message to put in place of one of ours.
+pub struct Job {
+	pub channel: ChannelId,
+	pub message: MessageId,
+	pub content: String,
+	pub via: Via,
+}
+
+/// What the bot does with each event. Runs on the workers, which see every event
+/// for a channel in order.
+pub struct Handler {
+	/// The quirks folder characters and settings are loaded from.
+	pub dir: PathBuf,
+	/// The account the bot runs as.
+	pub user: UserId,
+	pub api: Arc<dyn MessageApi>,
+	pub state: Arc<State>,
+}
+
+impl Handler {
+	/// Handles an event, leaving edits that fail to be retried from `edits`.
+	pub fn handle(&self, event: ChatEvent, edits: &mut EditQueue) {
+		match event {
+			ChatEvent::MessageCreate(message) if message.author == self.user => {
+				// Messages the bot resent in place of ours come back too.
+				if self.history().get(message.id).is_some_and(|e| e.quirked == message.content) {
+					return;
+				}
+				self.quirk(message, false, edits);
+			},
+			ChatEvent::MessageCreate(message) => {
+				// Other people's characters are remembered so mentions of them can use their handle.
+				let parsed = ParsedMessage::parse(&message.content);
+				if !parsed.characters().is_empty() {
+					self.state.mentions.record(&message, &Characters::from_message_in(&self.dir, parsed));
+				}
+			},
+			ChatEvent::MessageUpdate(message) if message.author == self.user => {
+				// The bot's own edits come back as updates too, and must be left alone.
+				if self.history().get(message.id).is_some_and(|e| e.quirked == message.content) {
+					return;
+				}
+				self.quirk(message, true, edits);
+			},
+			ChatEvent::MessageUpdate(_) => {},
+			ChatEvent::ReactionAdd(reaction) if reaction.user == self.user => self.react(reaction, edits),
+			ChatEvent::ReactionAdd(_) => {},
+			ChatEvent::Other => {},
+		}
+	}
+
+	/// Quirks one of our messages. Messages we `edited` ourselves are always quirked
+	/// in place, since they've already been posted.
+	fn quirk(&self, message: ChatMessage, edited: bool, edits: &mut EditQueue) {
+		println!("{}", message.content);
+		let parsed = ParsedMessage::parse(&message.content);
+		// These act on an earlier message, and are deleted rather than quirked.
+		match parsed.lines.as_slice() {
+			[Line::Global { name, args, .. }] if name == "undo" => {
+				// Left in place when there's nothing to undo, so it's clear it didn't work.
+				if let Some(target) = self.target(&message, args.get(0)) {
+					self.delete(&message);
+					self.undo(target, edits);
+				}
+				return;
+			},
+			[Line::Command { character, name, args, .. }] if name == "reroll" && Character::from_name_in(&self.dir, character).is_some() => {
+				if let Some(target) = self.target(&message, args.get(0)) {
+					self.delete(&message);
+					self.requirk(target, None, edits);
+				}
+				return;
+			},
+			_ => {},
+		}
+		let mut cs = Characters::from_message_in(&self.dir, parsed);
+		self.state.mentions.resolve(&*self.api, &message, &mut cs);
+		self.state.mentions.record(&message, &cs);
+		// Only this worker sees the channel, so its memo can be taken out while commands
+		// run without the other workers waiting on it.
+		let mut memo = self.memos().remove(&message.channel_id).unwrap_or_default();
+		let quirked_message = cs.quirked_in(&mut memo);
+		self.memos().insert(message.channel_id, memo);
+		if quirked_message != message.content {
+			println!("Quirkable message");
+			println!("{}", quirked_message);
+			// Webhooks post as a single character, so anything else is edited.
+			let via = match (cs.settings.delivery, cs.speaker()) {
+				_ if edited => Via::Edit,
+				(Delivery::Resend, _) => Via::Resend,
+				(Delivery::Webhook, Some(c)) if cs.characters.len() == 1 => Via::Webhook(Box::new(c.clone()), cs.dir.clone()),
+				_ => Via::Edit,
+			};
+			// Messages sent in place of ours are followed to their new ID once sent.
+			self.history().record(message.channel_id, message.id, &message.content, &quirked_message);
+			self.deliver(edits, Job {
+				channel: message.channel_id,
+				message: message.id,
+				content: quirked_message,
+				via,
+			});
+		}
+
+		// Presence changes are made by the event loop, which holds the connection.
+		let mut nicknames = self.state.nicknames.lock().unwrap_or_else(|e| e.into_inner());
+		if cs.presence().map(|p| p.presence) == Some(Presence::Offline) {
+			nicknames.restore(&*self.api, &cs.settings, message.channel_id);
+		} else if let Some(c) = cs.speaker() {
+			nicknames.follow(&*self.api, self.user, &cs.settings, message.channel_id, &c.handle);
+		}
+	}
+
+	/// The message a command acts on: the one it replies to, the one with the given
+	/// id, or else the last one the bot quirked in the channel. Only messages the bot
+	/// quirked in the command's channel count.
+	fn target(&self, command: &ChatMessage, id: Option<&str>) -> Option<MessageId> {
+		let history = self.history();
+		let target = match (command.reference, id) {
+			(Some(reference), _) => history.get(reference),
+			(None, Some(id)) => id.parse().ok().and_then(|id| history.get(MessageId(id))),
+			(None, None) => history.last_in(command.channel_id),
+		};
+		let target = target.filter(|e| e.channel == command.channel_id).map(|e| e.message);
+		if target.is_none() {
+			println!("No message to act on");
+		}
+		target
+	}
+
+	fn delete(&self, message: &ChatMessage) {
+		if let Err(err) = self.api.delete_message(message.channel_id, message.id) {
+			println!("Couldn't delete message {}: {}", message.id.0, err);
+		}
+	}
+
+	/// Puts a message the bot edited back to what we typed.
+	fn undo(&self, target: MessageId, edits: &mut EditQueue) {
+		// The original text comes back as an update, and is left alone since it now
+		// matches what the bot last wrote.
+		let entry = self.history().revert(target);
+		match entry {
+			Some(entry) => self.deliver(edits, Job {
+				channel: entry.channel,
+				message: entry.message,
+				via: edit_via(&entry),
+				content: entry.original,
+			}),
+			None => println!("Message {} wasn't quirked by the bot", target.0),
+		}
+	}
+
+	/// Does what our reaction's emoji is set up to do to a message the bot quirked,
+	/// then takes the reaction back off.
+	fn react(&self, reaction: ChatReaction, edits: &mut EditQueue) {
+		let webhook = match self.history().get(reaction.message_id) {
+			Some(entry) => entry.webhook,
+			None => return,
+		};
+		let action = match load_settings(&self.dir).reactions.get(&reaction.emoji) {
+			Some(action) => action.clone(),
+			None => return,
+		};
+		match action {
+			ReactionAction::Undo => self.undo(reaction.message_id, edits),
+			ReactionAction::Reroll => self.requirk(reaction.message_id, None, edits),
+			ReactionAction::Quirk(name) => self.requirk(reaction.message_id, Some(&name), edits),
+			ReactionAction::Delete => {
+				let deleted = if webhook {
+					self.api.delete_webhook_message(reaction.channel_id, reaction.message_id)
+				} else {
+					self.api.delete_message(reaction.channel_id, reaction.message_id)
+				};
+				if let Err(err) = deleted {
+					println!("Couldn't delete message {}: {}", reaction.message_id.0, err);
+				}
+				return;
+			},
+		}
+		if let Err(err) = self.api.remove_reaction(&reaction) {
+			println!("Couldn't remove reaction {}: {}", reaction.emoji, err);
+		}
+	}
+
+	/// Quirks what we typed for a message the bot edited again, for another go at
+	/// random replacements and scrambles. The dialogue can be given to another
+	/// `speaker`, though undoing still brings back what we typed.
+	fn requirk(&self, target: MessageId, speaker: Option<&str>, edits: &mut EditQueue) {
+		let entry = match self.history().get(target) {
+			Some(entry) => entry.clone(),
+			None => {
+				println!("Message {} wasn't quirked by the bot", target.0);
+				return;
+			},
+		};
+		let message = ChatMessage {
+			id: entry.message,
+			channel_id: entry.channel,
+			author: self.user,
+			content: entry.original.clone(),
+			mentions: Vec::new(),
+			reference: None,
+		};
+		let mut parsed = ParsedMessage::parse(&message.content);
+		if let Some(speaker) = speaker {
+			for line in parsed.lines.iter_mut() {
+				if let Line::Dialogue { character, .. } = line {
+					*character = speaker.to_string();
+				}
+			}
+		}
+		let mut cs = Characters::from_message_in(&self.dir, parsed);
+		self.state.mentions.resolve(&*self.api, &message, &mut cs);
+		// Commands already changed the memo the first time round, so they run on a copy.
+		let mut memo = self.memos().get(&entry.channel).cloned().unwrap_or_default();
+		let quirked = cs.quirked_in(&mut memo);
+		if quirked == entry.quirked {
+			println!("Quirking message {} again came out the same", target.0);
+			return;
+		}
+		self.history().record(entry.channel, entry.message, &entry.original, &quirked);
+		self.deliver(edits, Job {
+			channel: entry.channel,
+			message: entry.message,
+			content: quirked,
+			via: edit_via(&entry),
+		});
+	}
+
+	/// Puts a quirked message in place of ours. Edits that fail are left in `edits`
+	/// to be retried.
+	fn deliver(&self, edits: &mut EditQueue, job: Job) {
+		let sent = match &job.via {
+			Via::Edit => None,
+			Via::EditWebhook => {
+				edits.push_webhook(job.channel, job.message, &job.content);
+				return;
+			},
+			Via::Resend => Some(self.api.resend_message(job.channel, job.message, &job.content)),
+			Via::Webhook(c, dir) => Some(self.api.send_as(job.channel, job.message, c, dir, &job.content)),
+		};
+		match sent {
+			Some(Ok(id)) => {
+				let _ = self.api.delete_message(job.channel, job.message);
+				self.history().replace(job.message, id, matches!(job.via, Via::Webhook(..)));
+			},
+			Some(Err(err)) => {
+				println!("Couldn't send a new message, editing instead: {}", err);
+				edits.push(job.channel, job.message, &job.content);
+			},
+			None => edits.push(job.channel, job.message, &job.content),
+		}
+	}
+
+	fn memos(&self) -> MutexGuard<'_, HashMap<ChannelId, Memo>> {
+		self.state.memos.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	fn history(&self) -> MutexGuard<'_, History> {
+		self.state.history.lock().unwrap_or_else(|e| e.into_inner())
+	}
+}
+
+/// How to change a message the bot already quirked.
+fn edit_via(entry: &Entry) -> Via {
+	if entry.webhook { Via::EditWebhook } else { Via::Edit }
+}
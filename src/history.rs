@@ -0,0 +1,73 @@
+use std::collections::VecDeque;
+
+use discord::model::{ChannelId, MessageId};
+
+/// A message of ours the bot quirked, with the text we wrote and what it became.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+	pub channel: ChannelId,
+	pub message: MessageId,
+	pub original: String,
+	pub quirked: String,
+	/// Posted through a webhook, so only the webhook can change it.
+	pub webhook: bool,
+}
+
+/// The most recent messages the bot quirked, oldest first. Once full, the oldest
+/// are forgotten.
+pub struct History {
+	entries: VecDeque<Entry>,
+	pub capacity: usize,
+}
+
+impl Default for History {
+	fn default() -> History {
+		History {
+			entries: VecDeque::new(),
+			capacity: 200,
+		}
+	}
+}
+
+impl History {
+	/// Remembers a quirked message, replacing what was known about it before.
+	pub fn record(&mut self, channel: ChannelId, message: MessageId, original: &str, quirked: &str) {
+		let webhook = self.get(message).is_some_and(|e| e.webhook);
+		self.entries.retain(|e| e.message != message);
+		self.entries.push_back(Entry {
+			channel,
+			message,
+			original: original.to_string(),
+			quirked: quirked.to_string(),
+			webhook,
+		});
+		while self.entries.len() > self.capacity {
+			self.entries.pop_front();
+		}
+	}
+
+	/// Follows a quirked message to the one sent in its place, which was posted through
+	/// a webhook if `webhook` is set.
+	pub fn replace(&mut self, message: MessageId, sent: MessageId, webhook: bool) {
+		if let Some(entry) = self.entries.iter_mut().find(|e| e.message == message) {
+			entry.message = sent;
+			entry.webhook = webhook;
+		}
+	}
+
+	pub fn get(&self, message: MessageId) -> Option<&Entry> {
+		self.entries.iter().find(|e| e.message == message)
+	}
+
+	/// The newest message in the channel which is still quirked.
+	pub fn last_in(&self, channel: ChannelId) -> Option<&Entry> {
+		self.entries.iter().rev().find(|e| e.channel == channel && e.quirked != e.original)
+	}
+
+	/// Marks a message as put back to its original text, returning what's known about it.
+	pub fn revert(&mut self, message: MessageId) -> Option<Entry> {
+		let entry = self.entries.iter_mut().find(|e| e.message == message)?;
+		entry.quirked = entry.original.clone();
+		Some(entry.clone())
+	}
+}
@@ -1,15 +1,35 @@
 extern crate discord;
+extern crate rustblood_core;
 
 #[macro_use]
 extern crate self_update;
 
-use discord::model::Event;
-use discord::Discord;
+use rustblood_core::quirks_dir;
 
 use std::env;
+use std::process;
 
-pub mod quirk;
-pub mod tests;
+mod backend;
+mod backoff;
+mod bot;
+mod discord_backend;
+mod edits;
+mod handler;
+mod history;
+mod mentions;
+#[cfg(test)]
+mod mock;
+mod nicknames;
+mod resend;
+mod rest;
+mod webhooks;
+mod workers;
+
+mod tests;
+
+use backend::ChatBackend;
+use bot::Bot;
+use discord_backend::DiscordBackend;
 
 fn main() {
 	let status = self_update::backends::github::Update::configure()
@@ -23,40 +43,22 @@ fn main() {
 
     println!("Update status: `{}`!", status.version());
 
-	let discord = Discord::from_user_token(&env::var("DISCORD_TOKEN").expect("Expected token"))
-		.expect("login failed");
-
-	// Establish and use a websocket connection
-	let connection = discord.connect();
-    let (mut connection, _) = match connection {
-        Ok(res) => res,
-        Err(err) => {
-            panic!("Error: {}", err);
-        },
-    };
-    
-    let bot_id = discord.get_current_user().unwrap().id;
-	println!("Ready. {}", bot_id);
-	loop {
-		match connection.recv_event() {
-			Ok(Event::MessageCreate(message)) => {
-                if message.author.id == bot_id {
-                    println!("{}", message.content);
-					let cs = quirk::Characters::from_string(&message.content);
-					let quirked_message = &cs.quirked();
-					if quirked_message != &message.content {
-						println!("Quirkable message");
-						println!("{}", quirked_message);
-						discord.edit_message(message.channel_id, message.id, quirked_message).unwrap();
-					}
-                }
-			}
-			Ok(_) => {}
-			Err(discord::Error::Closed(code, body)) => {
-				println!("Gateway closed on us with code {:?}: {}", code, body);
-				break;
-			}
-			Err(_) => {},
-		}
-	}
+	let token = env::var("DISCORD_TOKEN").expect("Expected token");
+	let mut backend = match DiscordBackend::connect(&token) {
+		Ok(backend) => backend,
+		Err(err) => {
+			panic!("Error: {}", err);
+		},
+	};
+	println!("Ready. {}", backend.current_user());
+
+	let mut bot = Bot::new(quirks_dir());
+	let shutdown = bot.shutdown(&backend);
+	ctrlc::set_handler(move || {
+		println!("Shutting down");
+		shutdown.run();
+		process::exit(0);
+	}).expect("Couldn't listen for Ctrl+C");
+	let closed = bot.run(&mut backend);
+	println!("Giving up on the gateway: {}", closed);
 }
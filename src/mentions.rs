@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use discord::model::{ChannelId, UserId};
+use regex::{Captures, Regex};
+use rustblood_core::{Args, Characters, Line};
+
+use crate::backend::{ChatMessage, MessageApi};
+
+/// Remembers which character each user last played in each channel, and uses it to
+/// turn Discord mentions in command arguments into names that read well in a memo.
+/// Shared between the workers.
+#[derive(Default)]
+pub struct Mentions {
+	last_played: Mutex<HashMap<(ChannelId, UserId), String>>,
+}
+
+impl Mentions {
+	/// Records the handle of the last loaded character a message speaks as, if any.
+	pub fn record(&self, message: &ChatMessage, cs: &Characters) {
+		if let Some(c) = cs.speaker() {
+			self.last_played.lock().unwrap_or_else(|e| e.into_inner()).insert((message.channel_id, message.author), c.handle.clone());
+		}
+	}
+
+	/// Replaces user mentions such as `<@123>` in action command arguments with the
+	/// handle of the character that user last played here, or their display name.
+	/// Only commands a loaded character knows are changed. Anything else is sent as
+	/// typed, so its mentions still work.
+	pub fn resolve(&self, api: &dyn MessageApi, message: &ChatMessage, cs: &mut Characters) {
+		let regex = Regex::new(r"<@!?(?P<id>\d+)>").unwrap();
+		for line in cs.message.lines.iter_mut() {
+			if let Line::Command { character, name, args, .. } = line {
+				let known = cs.characters.get(character.as_str()).is_some_and(|c| c.has_command(name));
+				if !known || !regex.is_match(args.raw()) {
+					continue;
+				}
+				let raw = regex.replace_all(args.raw(), |caps: &Captures| {
+					match caps["id"].parse::<u64>() {
+						Ok(id) => quote(&self.name_of(api, message, UserId(id))),
+						Err(_) => caps[0].to_string(),
+					}
+				});
+				*args = Args::parse(&raw);
+			}
+		}
+	}
+
+	fn name_of(&self, api: &dyn MessageApi, message: &ChatMessage, id: UserId) -> String {
+		let handle = self.last_played.lock().unwrap_or_else(|e| e.into_inner()).get(&(message.channel_id, id)).cloned();
+		if let Some(handle) = handle {
+			return handle;
+		}
+
+		let nick = api.server_of(message.channel_id)
+			.and_then(|server| api.nickname(server, id).ok().flatten());
+		nick.or_else(|| message.mentions.iter().find(|(user, _)| *user == id).map(|(_, name)| name.clone()))
+			.unwrap_or_else(|| format!("<@{}>", id.0))
+	}
+}
+
+/// Quotes a name so that it stays a single command argument.
+fn quote(s: &str) -> String {
+	format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,310 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use discord::model::{ChannelId, MessageId, ServerId, UserId};
+use rustblood_core::{Character, PresenceChange};
+
+use crate::backend::{ChatBackend, ChatError, ChatEvent, ChatMessage, ChatReaction, ChatResult, MessageApi};
+
+/// An in-memory chat service which plays back queued events and records
+/// everything the bot does in response.
+pub struct MockBackend {
+	pub user: UserId,
+	pub events: VecDeque<ChatResult<ChatEvent>>,
+	/// How many more times reconnecting fails before it works.
+	pub reconnect_failures: u32,
+	/// What reconnecting fails with.
+	pub reconnect_error: ChatError,
+	pub reconnects: u32,
+	pub presences: Vec<PresenceChange>,
+	pub messages: Arc<MockMessages>,
+}
+
+impl MockBackend {
+	pub fn new(user: UserId) -> MockBackend {
+		MockBackend {
+			user,
+			events: VecDeque::new(),
+			reconnect_failures: 0,
+			reconnect_error: ChatError::Other("reconnect failed".to_string()),
+			reconnects: 0,
+			presences: Vec::new(),
+			messages: Arc::new(MockMessages::new(user)),
+		}
+	}
+
+	/// Queues a message from the given user, returning it so tests can refer to it.
+	pub fn message(&mut self, id: u64, channel: u64, author: UserId, content: &str) -> ChatMessage {
+		let message = ChatMessage {
+			id: MessageId(id),
+			channel_id: ChannelId(channel),
+			author,
+			content: content.to_string(),
+			mentions: Vec::new(),
+			reference: None,
+		};
+		self.events.push_back(Ok(ChatEvent::MessageCreate(message.clone())));
+		message
+	}
+
+	/// Queues a message from the given user replying to another.
+	pub fn reply(&mut self, id: u64, channel: u64, author: UserId, content: &str, to: u64) {
+		self.events.push_back(Ok(ChatEvent::MessageCreate(ChatMessage {
+			id: MessageId(id),
+			channel_id: ChannelId(channel),
+			author,
+			content: content.to_string(),
+			mentions: Vec::new(),
+			reference: Some(MessageId(to)),
+		})));
+	}
+
+	/// Queues an edit to a message, as if the given user had changed its text.
+	pub fn update(&mut self, id: u64, channel: u64, author: UserId, content: &str) {
+		self.events.push_back(Ok(ChatEvent::MessageUpdate(ChatMessage {
+			id: MessageId(id),
+			channel_id: ChannelId(channel),
+			author,
+			content: content.to_string(),
+			mentions: Vec::new(),
+			reference: None,
+		})));
+	}
+
+	/// Queues a reaction from the given user.
+	pub fn react(&mut self, message: u64, channel: u64, user: UserId, emoji: &str) {
+		self.events.push_back(Ok(ChatEvent::ReactionAdd(ChatReaction {
+			channel_id: ChannelId(channel),
+			message_id: MessageId(message),
+			user,
+			emoji: emoji.to_string(),
+			emoji_id: None,
+		})));
+	}
+}
+
+impl ChatBackend for MockBackend {
+	fn current_user(&self) -> UserId {
+		self.user
+	}
+
+	/// Once the queued events run out, the connection closes with a code that
+	/// stops the bot, as an invalid token would.
+	fn recv_event(&mut self) -> ChatResult<ChatEvent> {
+		if let Some(echo) = self.messages.echoes.lock().unwrap().pop_front() {
+			return Ok(echo);
+		}
+		self.events.pop_front().unwrap_or_else(|| Err(ChatError::Closed(Some(4004), "no more events".to_string())))
+	}
+
+	fn reconnect(&mut self) -> ChatResult<()> {
+		if self.reconnect_failures > 0 {
+			self.reconnect_failures -= 1;
+			return Err(self.reconnect_error.clone());
+		}
+		self.reconnects += 1;
+		Ok(())
+	}
+
+	fn messages(&self) -> Arc<dyn MessageApi> {
+		self.messages.clone()
+	}
+
+	fn set_presence(&mut self, change: PresenceChange) {
+		self.presences.push(change);
+	}
+}
+
+/// Records the messages sent and changed through it, from any thread.
+pub struct MockMessages {
+	/// The account messages are sent as, whose nickname `set_nickname` changes.
+	pub user: UserId,
+	pub servers: Mutex<HashMap<ChannelId, ServerId>>,
+	pub nicknames: Mutex<HashMap<(ServerId, UserId), String>>,
+	/// Methods named here fail with an error instead of doing anything.
+	pub failing: Mutex<Vec<&'static str>>,
+	/// Methods named here panic.
+	pub panicking: Mutex<Vec<&'static str>>,
+	/// Errors the next edits fail with, before edits start working again.
+	pub edit_errors: Mutex<VecDeque<ChatError>>,
+
+	pub edited: Mutex<Vec<(ChannelId, MessageId, String)>>,
+	pub deleted: Mutex<Vec<(ChannelId, MessageId)>>,
+	pub resent: Mutex<Vec<(ChannelId, MessageId, String)>>,
+	/// Messages posted as a character, with the character's handle.
+	pub sent_as: Mutex<Vec<(ChannelId, String, String)>>,
+	pub webhook_edited: Mutex<Vec<(ChannelId, MessageId, String)>>,
+	pub webhook_deleted: Mutex<Vec<(ChannelId, MessageId)>>,
+	pub unreacted: Mutex<Vec<(MessageId, String)>>,
+	/// How many messages have been sent in place of others, which get IDs from 1000 up.
+	pub replacements: Mutex<u64>,
+	/// Events for messages the bot sent as us, played back before any queued events.
+	pub echoes: Mutex<VecDeque<ChatEvent>>,
+}
+
+impl Default for MockMessages {
+	fn default() -> MockMessages {
+		MockMessages::new(UserId(0))
+	}
+}
+
+impl MockMessages {
+	pub fn new(user: UserId) -> MockMessages {
+		MockMessages {
+			user,
+			servers: Mutex::default(),
+			nicknames: Mutex::default(),
+			failing: Mutex::default(),
+			panicking: Mutex::default(),
+			edit_errors: Mutex::default(),
+			edited: Mutex::default(),
+			deleted: Mutex::default(),
+			resent: Mutex::default(),
+			sent_as: Mutex::default(),
+			webhook_edited: Mutex::default(),
+			webhook_deleted: Mutex::default(),
+			unreacted: Mutex::default(),
+			replacements: Mutex::default(),
+			echoes: Mutex::default(),
+		}
+	}
+
+	/// Puts a channel in a server.
+	pub fn add_channel(&self, channel: ChannelId, server: ServerId) {
+		self.servers.lock().unwrap().insert(channel, server);
+	}
+
+	pub fn set_nickname_of(&self, server: ServerId, user: UserId, nickname: &str) {
+		self.nicknames.lock().unwrap().insert((server, user), nickname.to_string());
+	}
+
+	pub fn nickname_of(&self, server: ServerId, user: UserId) -> Option<String> {
+		self.nicknames.lock().unwrap().get(&(server, user)).cloned()
+	}
+
+	pub fn fail(&self, method: &'static str) {
+		self.failing.lock().unwrap().push(method);
+	}
+
+	pub fn edited(&self) -> Vec<(ChannelId, MessageId, String)> {
+		self.edited.lock().unwrap().clone()
+	}
+
+	pub fn deleted(&self) -> Vec<(ChannelId, MessageId)> {
+		self.deleted.lock().unwrap().clone()
+	}
+
+	pub fn resent(&self) -> Vec<(ChannelId, MessageId, String)> {
+		self.resent.lock().unwrap().clone()
+	}
+
+	pub fn sent_as(&self) -> Vec<(ChannelId, String, String)> {
+		self.sent_as.lock().unwrap().clone()
+	}
+
+	pub fn webhook_edited(&self) -> Vec<(ChannelId, MessageId, String)> {
+		self.webhook_edited.lock().unwrap().clone()
+	}
+
+	pub fn webhook_deleted(&self) -> Vec<(ChannelId, MessageId)> {
+		self.webhook_deleted.lock().unwrap().clone()
+	}
+
+	pub fn unreacted(&self) -> Vec<(MessageId, String)> {
+		self.unreacted.lock().unwrap().clone()
+	}
+
+	fn replacement(&self) -> MessageId {
+		let mut replacements = self.replacements.lock().unwrap();
+		*replacements += 1;
+		MessageId(999 + *replacements)
+	}
+
+	fn check(&self, method: &str) -> ChatResult<()> {
+		if self.panicking.lock().unwrap().contains(&method) {
+			panic!("{} panicked", method);
+		}
+		if self.failing.lock().unwrap().contains(&method) {
+			return Err(ChatError::Other(format!("{} failed", method)));
+		}
+		Ok(())
+	}
+}
+
+impl MessageApi for MockMessages {
+	fn edit_message(&self, channel: ChannelId, message: MessageId, content: &str) -> ChatResult<()> {
+		self.check("edit_message")?;
+		if let Some(err) = self.edit_errors.lock().unwrap().pop_front() {
+			return Err(err);
+		}
+		self.edited.lock().unwrap().push((channel, message, content.to_string()));
+		Ok(())
+	}
+
+	fn delete_message(&self, channel: ChannelId, message: MessageId) -> ChatResult<()> {
+		self.check("delete_message")?;
+		self.deleted.lock().unwrap().push((channel, message));
+		Ok(())
+	}
+
+	fn resend_message(&self, channel: ChannelId, message: MessageId, content: &str) -> ChatResult<MessageId> {
+		self.check("resend_message")?;
+		self.resent.lock().unwrap().push((channel, message, content.to_string()));
+		let id = self.replacement();
+		self.echoes.lock().unwrap().push_back(ChatEvent::MessageCreate(ChatMessage {
+			id,
+			channel_id: channel,
+			author: self.user,
+			content: content.to_string(),
+			mentions: Vec::new(),
+			reference: None,
+		}));
+		Ok(id)
+	}
+
+	fn send_as(&self, channel: ChannelId, _message: MessageId, c: &Character, _dir: &Path, content: &str) -> ChatResult<MessageId> {
+		self.check("send_as")?;
+		self.sent_as.lock().unwrap().push((channel, c.handle.clone(), content.to_string()));
+		Ok(self.replacement())
+	}
+
+	fn edit_webhook_message(&self, channel: ChannelId, message: MessageId, content: &str) -> ChatResult<()> {
+		self.check("edit_webhook_message")?;
+		self.webhook_edited.lock().unwrap().push((channel, message, content.to_string()));
+		Ok(())
+	}
+
+	fn delete_webhook_message(&self, channel: ChannelId, message: MessageId) -> ChatResult<()> {
+		self.check("delete_webhook_message")?;
+		self.webhook_deleted.lock().unwrap().push((channel, message));
+		Ok(())
+	}
+
+	fn remove_reaction(&self, reaction: &ChatReaction) -> ChatResult<()> {
+		self.check("remove_reaction")?;
+		self.unreacted.lock().unwrap().push((reaction.message_id, reaction.emoji.clone()));
+		Ok(())
+	}
+
+	fn server_of(&self, channel: ChannelId) -> Option<ServerId> {
+		self.check("server_of").ok()?;
+		self.servers.lock().unwrap().get(&channel).copied()
+	}
+
+	fn nickname(&self, server: ServerId, user: UserId) -> ChatResult<Option<String>> {
+		self.check("nickname")?;
+		Ok(self.nickname_of(server, user))
+	}
+
+	fn set_nickname(&self, server: ServerId, nickname: &str) -> ChatResult<()> {
+		self.check("set_nickname")?;
+		let mut nicknames = self.nicknames.lock().unwrap();
+		if nickname.is_empty() {
+			nicknames.remove(&(server, self.user));
+		} else {
+			nicknames.insert((server, self.user), nickname.to_string());
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::time::{Duration, Instant};
+
+use discord::model::{ChannelId, ServerId, UserId};
+use rustblood_core::Settings;
+
+use crate::backend::MessageApi;
+
+/// Keeps our nickname in opted-in servers in step with the character we speak as,
+/// remembering the nickname we had before so it can be put back.
+#[derive(Default)]
+pub struct Nicknames {
+	originals: HashMap<ServerId, Option<String>>,
+	current: HashMap<ServerId, String>,
+	changed_at: HashMap<ServerId, Instant>,
+}
+
+impl Nicknames {
+	/// Changes our nickname, as `user`, to `handle` in the server of the given channel,
+	/// if that server has opted in and the cooldown since the last change has passed.
+	pub fn follow(&mut self, api: &dyn MessageApi, user: UserId, settings: &Settings, channel: ChannelId, handle: &str) {
+		let server = match server_of(api, settings, channel) {
+			Some(server) => server,
+			None => return,
+		};
+		if self.current.get(&server).map(String::as_str) == Some(handle) {
+			return;
+		}
+		let cooldown = Duration::from_secs(settings.nickname_cooldown);
+		if self.changed_at.get(&server).is_some_and(|t| t.elapsed() < cooldown) {
+			return;
+		}
+
+		if let Entry::Vacant(original) = self.originals.entry(server) {
+			match api.nickname(server, user) {
+				Ok(nick) if nick.as_deref() == Some(handle) => return,
+				Ok(nick) => { original.insert(nick); },
+				Err(err) => {
+					println!("Couldn't look up our nickname in {}: {}", server.0, err);
+					return;
+				},
+			}
+		}
+		match api.set_nickname(server, handle) {
+			Ok(()) => {
+				self.current.insert(server, handle.to_string());
+				self.changed_at.insert(server, Instant::now());
+			},
+			Err(err) => println!("Couldn't change our nickname in {}: {}", server.0, err),
+		}
+	}
+
+	/// Puts back the nickname we had before in the server of the given channel.
+	pub fn restore(&mut self, api: &dyn MessageApi, settings: &Settings, channel: ChannelId) {
+		if let Some(server) = server_of(api, settings, channel) {
+			self.restore_server(api, server);
+		}
+	}
+
+	/// Puts back the nickname we had before in every server we changed it in.
+	pub fn restore_all(&mut self, api: &dyn MessageApi) {
+		let servers: Vec<ServerId> = self.originals.keys().copied().collect();
+		for server in servers {
+			self.restore_server(api, server);
+		}
+	}
+
+	fn restore_server(&mut self, api: &dyn MessageApi, server: ServerId) {
+		let original = match self.originals.remove(&server) {
+			Some(original) => original,
+			None => return,
+		};
+		self.current.remove(&server);
+		// An empty nickname clears it, leaving our username.
+		match api.set_nickname(server, original.as_deref().unwrap_or("")) {
+			Ok(()) => { self.changed_at.insert(server, Instant::now()); },
+			Err(err) => println!("Couldn't restore our nickname in {}: {}", server.0, err),
+		}
+	}
+}
+
+/// The server a channel belongs to, if it has opted in to nickname changes.
+fn server_of(api: &dyn MessageApi, settings: &Settings, channel: ChannelId) -> Option<ServerId> {
+	if settings.nickname_servers.is_empty() {
+		return None;
+	}
+	api.server_of(channel).filter(|s| settings.nickname_servers.contains(&s.0))
+}
@@ -0,0 +1,54 @@
+use discord::model::{ChannelId, MessageId};
+use serde_json::{json, Value};
+
+use crate::backend::{ChatError, ChatResult};
+use crate::rest::Rest;
+
+/// Sends `content` as a new message in place of one of ours, replying to the same
+/// message and carrying over its attachments where they can be downloaded. The
+/// original is left for the caller to delete once this succeeds. Returns the new
+/// message's ID.
+pub fn resend(rest: &Rest, channel: ChannelId, message: MessageId, content: &str) -> ChatResult<MessageId> {
+	let original = rest.get(&format!("/channels/{}/messages/{}", channel.0, message.0))?;
+
+	// Everyone mentioned was already notified by the original, so don't ping them twice.
+	let mut body = json!({
+		"content": content,
+		"allowed_mentions": { "parse": [], "replied_user": false },
+	});
+	if let Some(id) = original["message_reference"]["message_id"].as_str() {
+		body["message_reference"] = json!({ "message_id": id, "fail_if_not_exists": false });
+	}
+
+	let files = attachments(rest, &original);
+	let path = format!("/channels/{}/messages", channel.0);
+	let sent = if files.is_empty() {
+		rest.post(&path, &body)?
+	} else {
+		rest.post_with_files(&path, &body, files)?
+	};
+	message_id(&sent)
+}
+
+/// Downloads a message's attachments as file names and contents, leaving out any
+/// that can't be downloaded.
+pub fn attachments(rest: &Rest, message: &Value) -> Vec<(String, Vec<u8>)> {
+	let mut files = Vec::new();
+	for attachment in message["attachments"].as_array().into_iter().flatten() {
+		if let (Some(url), Some(name)) = (attachment["url"].as_str(), attachment["filename"].as_str()) {
+			match rest.download(url) {
+				Ok(data) => files.push((name.to_string(), data)),
+				Err(err) => println!("Couldn't carry over attachment {}: {}", name, err),
+			}
+		}
+	}
+	files
+}
+
+/// The ID of a message Discord sent back.
+pub fn message_id(message: &Value) -> ChatResult<MessageId> {
+	message["id"].as_str()
+		.and_then(|id| id.parse().ok())
+		.map(MessageId)
+		.ok_or_else(|| ChatError::Other("Discord didn't say which message was sent".to_string()))
+}
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use reqwest::blocking::multipart::{Form, Part};
+use reqwest::blocking::{Client, RequestBuilder};
+use serde_json::Value;
+
+use crate::backend::{ChatError, ChatResult};
+
+pub const API: &str = "https://discord.com/api/v9";
+
+/// A small client for the parts of Discord's HTTP API that discord-rs doesn't cover.
+#[derive(Clone)]
+pub struct Rest {
+	token: String,
+	client: Client,
+}
+
+impl Rest {
+	pub fn new(token: &str) -> Rest {
+		Rest {
+			token: token.to_string(),
+			client: Client::new(),
+		}
+	}
+
+	pub fn get(&self, path: &str) -> ChatResult<Value> {
+		self.send(self.client.get(format!("{}{}", API, path)))
+	}
+
+	pub fn post(&self, path: &str, body: &Value) -> ChatResult<Value> {
+		self.send(self.client.post(format!("{}{}", API, path)).json(body))
+	}
+
+	pub fn patch(&self, path: &str, body: &Value) -> ChatResult<Value> {
+		self.send(self.client.patch(format!("{}{}", API, path)).json(body))
+	}
+
+	pub fn delete(&self, path: &str) -> ChatResult<Value> {
+		self.send(self.client.delete(format!("{}{}", API, path)))
+	}
+
+	/// Posts a message body along with files, given as file names and contents.
+	pub fn post_with_files(&self, path: &str, body: &Value, files: Vec<(String, Vec<u8>)>) -> ChatResult<Value> {
+		let mut form = Form::new().text("payload_json", body.to_string());
+		for (i, (name, data)) in files.into_iter().enumerate() {
+			form = form.part(format!("files[{}]", i), Part::bytes(data).file_name(name));
+		}
+		self.send(self.client.post(format!("{}{}", API, path)).multipart(form))
+	}
+
+	/// Downloads a file, such as an attachment, from Discord's CDN.
+	pub fn download(&self, url: &str) -> ChatResult<Vec<u8>> {
+		self.client.get(url)
+			.send()
+			.and_then(|r| r.error_for_status())
+			.and_then(|r| r.bytes())
+			.map(|b| b.to_vec())
+			.map_err(other)
+	}
+
+	fn send(&self, request: RequestBuilder) -> ChatResult<Value> {
+		let response = request.header("Authorization", &self.token).send().map_err(other)?;
+		let retry_after = response.headers().get("Retry-After").and_then(|v| v.to_str().ok());
+		if let Some(err) = status_error(response.status().as_u16(), retry_after) {
+			return Err(err);
+		}
+		let response = response.error_for_status().map_err(other)?;
+		// Some endpoints answer with no content at all.
+		let text = response.text().map_err(other)?;
+		if text.is_empty() {
+			return Ok(Value::Null);
+		}
+		serde_json::from_str(&text).map_err(|e| ChatError::Other(e.to_string()))
+	}
+}
+
+/// The error for a response status that callers handle differently from other
+/// failures, given the Retry-After header in seconds if there was one.
+pub fn status_error(status: u16, retry_after: Option<&str>) -> Option<ChatError> {
+	match status {
+		401 => Some(ChatError::Unauthorized),
+		404 => Some(ChatError::NotFound),
+		429 => {
+			let seconds = retry_after.and_then(|s| s.parse::<f64>().ok()).unwrap_or(1.0);
+			Some(ChatError::RateLimited(Duration::from_secs_f64(seconds)))
+		},
+		_ => None,
+	}
+}
+
+fn other(err: reqwest::Error) -> ChatError {
+	ChatError::Other(err.to_string())
+}
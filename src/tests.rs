@@ -1,823 +1,951 @@
 #[cfg(test)]
-mod quirks {
-    use crate::quirk::{self, Characters};
-    use crate::quirk::Character;
-    use test_case::test_case;
-
-    #[test_case(false ; "without unicode")]
-    #[test_case(true ; "with unicode")]
-    fn can_serialize_deserialize(unicode: bool) {
-        let data;
-        if !unicode {
-            data = r#"
-            // 'quirks' is an array of dicts that describes operations to be done on a string. Operations are done in the order these quirks are described in.
-            // 'handle' is just a convenience for the script to display for debugging.
-            // 'acronym' is used to construct the final message. Allows for characters with the same acronym to be used, just with different filenames to reference them. Optional.
-            //
-            // Possible quirks:
-            //                  prefix:
-            //                  Adds the following characters to the beginning of the string.
-            //
-            //                  suffix:
-            //                  Adds the following characters to the end of the string.
-            //
-            //                  simple_replacements:
-            //                  For each replacement operation described, changes all occurances of the supplied character sequences inside the string.
-            //                  Operations occur in their described order.
-            //
-            //                  random_replacements:
-            //                  For each replacement operation described, replaces all occurances of the supplied character sequence inside the string with a random selection.
-            //                  Operations occut in their described order.
-            //
-            //                  scramble:
-            //                  For each operation described, changes all occurances of the supplied character sequence inside the string with a random arrangement of the characters supplied.
-            //                  Operations occur in their described order.
-            //
-            //                  style:
-            //                  Describes a typing style. If this is specified, the script will attempt to apply special operations.
-            //                  Supported values: lowercase, uppercase, alternating, camelcase, reverse, inverted
-            //
-            //                  WARNING!!! Make sure to only include the following structure in your quirk file. Including these comments inside the file will render it inoperable.
-            {
-                "handle": "yourHandle",
-                "acronym": "YH",
-                "quirks": [
-                    {
-                        "prefix": ">>> "
-                    },
-                    {
-                        "simple_replacements": [
-                            [
-                                "z", "zee"
-                            ]
-                        ]
-                    },
-                    {
-                        "random_replacements": [
-                            [
-                                "lmao", ["laughing my ass off", "assing my laugh off", "hahahaha"] // lol
-                            ]
-                        ]
-                    },
-                    {
-                        "scramble": [
-                            [
-                                "fuck", "%&#@"
-                            ],
-                            [
-                                "shit", "%&!@"
-                            ],
-                            [
-                                "ass", "@$%"
-                            ]
-                        ]
-                    },
-                    {
-                        "suffix": " <<<"
-                    },
-                    {
-                        "style": "lowercase"
-                    }
-                ]
-            }
-            "#;
-        } else {
-            data = r#"
-            {
-                // Comment
-                // Test goes
-                // Here
-                "handle": "lowhungNoose",
-                "acronym": "LN",
-                "quirks": [
-                    {
-                        "prefix": "C>lll= "
-                    },
-                    {
-                        "simple_replacements": [
-                            [
-                                "A", "Д"
-                            ],
-                            [
-                                "a", "д"
-                            ],
-                            [
-                                "E", "Э"
-                            ],
-                            [
-                                "e", "э"
-                            ],
-                            [
-                                "B", "В"
-                            ],
-                            [
-                                "b", "ѣ"
-                            ],
-                            [
-                                "r","г"
-                            ],
-                            [
-                                "R", "Я"
-                            ],
-                            [
-                                "W", "Ш"
-                            ],
-                            [
-                                "T", "Т"
-                            ],
-                            [
-                                "t", "т"
-                            ],
-                            [
-                                "O", "Ø"
-                            ],
-                            [
-                                "V", "Ѵ"
-                            ],
-                            [
-                                "v", "ѵ"
-                            ],
-                            [
-                                "io", "Ю"
-                            ],
-                            [
-                                "X", "Ж"
-                            ],
-                            [
-                                "x", "ж"
-                            ],
-                            [
-                                "N", "И"
-                            ],
-                            [
-                                "n", "л"
-                            ],
-                            [
-                                "m", "м"
-                            ],
-                            [
-                                "Y", "Ч"
-                            ],
-                            [ 
-                                "y", "ч"
-                            ]
-                        ]
-                    },
-                    {
-                        "suffix": " ==" // Comment test
-                    }
-                ]
-            }
-            "#;
-        }
-        
-    
-        let c = quirk::parse_safe(data.to_string());
-    
-        let j = serde_json::to_string_pretty(&c).unwrap();
-    
-        assert!(c.quirks.len() > 0); // Our quirks vec actually has content
-        if unicode {
-            assert!(c.quirks[0]["prefix"] == "C>lll= "); // The first element of quirks is what we expect
-            assert!(c.quirks[2]["suffix"] == " =="); // The last element of quirks is what we expect
-        } else {
-            assert!(c.quirks[0]["prefix"] == ">>> "); // The first element of quirks is what we expect
-            assert!(c.quirks[5]["style"] == "lowercase"); // The last element of quirks is what we expect
-        }
-        assert!(quirk::parse_safe(j) == c); // The values did not mutate for no reason
-    
-    }
-    
-    #[test_case(">>> " ; "without unicode")]
-    #[test_case("𝖟∞ " ; "with unicode")]
-    fn can_prefix(s: &str) {
-        let data = r#"
-        {
-            "handle": "yourHandle",
-            "acronym": "YH",
-            "quirks": [
-                {
-                    "prefix": ""
-                }
-            ]
-        }
-        "#;
-        let data = data.replace("\"\"", &format!("\"{}\"", s));
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = "The quick brown fox jumped over the lazy dog.";
-    
-        let mutated_string = c.quirked(test_string);
-    
-        assert!(mutated_string == format!("YH: {}The quick brown fox jumped over the lazy dog.", s));
-    }
-    
-    #[test_case(" <<<" ; "without unicode")]
-    #[test_case(" 𝖟∞" ; "with unicode")]
-    fn can_suffix(s: &str) {
-        let data = r#"
-        {
-            "handle": "yourHandle",
-            "acronym": "YH",
-            "quirks": [
-                {
-                    "suffix": ""
-                }
-            ]
-        }
-        "#;
-        let data = data.replace("\"\"", &format!("\"{}\"", s));
-
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = "The quick brown fox jumped over the lazy dog.";
-    
-        let mutated_string = c.quirked(test_string);
-    
-        assert!(mutated_string == format!("YH: The quick brown fox jumped over the lazy dog.{}", s));
-    }
-    
-    #[test_case("zee" ; "without unicode")]
-    #[test_case("z𝖟𝖟" ; "with unicode")]
-    fn can_simple_replace(s: &str) {
-        let data = r#"
-        {
-            "handle": "yourHandle",
-            "acronym": "YH",
-            "quirks": [
-                {
-                    "simple_replacements": [
-                        [
-                            "z", ""
-                        ],
-                        [
-                            "The", "Da"
-                        ]
-                    ]
-                }
-            ]
-        }
-        "#;
-        let data = data.replace("\"\"", &format!("\"{}\"", s));
-
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = "The quick brown fox jumped over the lazy dog.";
-    
-        let mutated_string = c.quirked(test_string);
-        
-        assert!(mutated_string == format!("YH: Da quick brown fox jumped over the la{}y dog.", s));
-    }
-    
-    #[test_case("red", "blue", "green" ; "without unicode")]
-    #[test_case("𝓻𝖟𝓭", "𝓫𝓵𝓊𝓮", "𝓰𝓻𝓮𝓮𝓷" ; "with unicode")]
-    fn can_random_replace(s1: &str, s2: &str, s3: &str) {
-        let data = r#"
-        {
-            "handle": "yourHandle",
-            "acronym": "YH",
-            "quirks": [
-                {
-                    "random_replacements": [
-                        [
-                            "brown", ["s1", "s2", "s3"]
-                        ]
-                    ]
-                }
-            ]
-        }
-        "#;
-
-        let data = data.replace("\"s1\"", &format!("\"{}\"", s1));
-        let data = data.replace("\"s2\"", &format!("\"{}\"", s2));
-        let data = data.replace("\"s3\"", &format!("\"{}\"", s3));
-
-
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = "The quick brown fox jumped over the lazy dog.";
-    
-        let mutated_string = c.quirked(test_string);
-    
-        assert!(mutated_string == format!("YH: The quick {} fox jumped over the lazy dog.", s1) ||
-                mutated_string == format!("YH: The quick {} fox jumped over the lazy dog.", s2) ||
-                mutated_string == format!("YH: The quick {} fox jumped over the lazy dog.", s3));
-    }
-    
-    #[test_case("(?P<y>\\\\d{4})-(?P<m>\\\\d{2})-(?P<d>\\\\d{2})", "$m/$d/$y" ; "without unicode")]
-    #[test_case("(?P<y>\\\\d{4})-(?P<m>\\\\d{2})-(?P<d>\\\\d{2})", "$m𝓵$d𝓵$y" ; "with unicode")]
-    fn can_regex_replace(s1: &str, s2: &str) {
-        let data = r#"
-        {
-            "handle": "yourHandle",
-            "acronym": "YH",
-            "quirks": [
-                {
-                    "regex_replacements": [
-                        [
-                            "s1", "s2"
-                        ]
-                    ]
-                }
-            ]
-        }
-        "#;
-
-        let data = data.replace("\"s1\"", &format!("\"{}\"", s1));
-        let data = data.replace("\"s2\"", &format!("\"{}\"", s2));
-
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = "The quick 2014-01-01 brown fox jumped over the lazy dog.";
-    
-        let mutated_string = c.quirked(test_string);
-    
-        assert!(mutated_string == "YH: The quick 01/01/2014 brown fox jumped over the lazy dog." ||
-                mutated_string == "YH: The quick 01𝓵01𝓵2014 brown fox jumped over the lazy dog.");
-    }
-
-    #[test_case("red", "blue", "green", false ; "without unicode")]
-    #[test_case("𝓻𝖟𝓭", "𝓫𝓵𝓊𝓮", "𝓰𝓻𝓮𝓮𝓷", true ; "with unicode")]
-    fn can_scramble(s1: &str, s2: &str, s3: &str, unicode: bool) {
-        let data = r#"
-        {
-            "handle": "yourHandle",
-            "acronym": "YH",
-            "quirks": [
-                {
-                    "scramble": [
-                        [
-                            "quick", "s1"
-                        ],
-                        [
-                            "jumped", "s2"
-                        ],
-                        [
-                            "lazy", "s3"
-                        ]
-                    ]
-                }
-            ]
-        }
-        "#;
-        let data = data.replace("\"s1\"", &format!("\"{}\"", s1));
-        let data = data.replace("\"s2\"", &format!("\"{}\"", s2));
-        let data = data.replace("\"s3\"", &format!("\"{}\"", s3));
-
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = "The quick brown fox jumped over the lazy dog.";
-    
-        let mutated_string = c.quirked(test_string);
-        
-        assert!(!(mutated_string.contains("quick")) && !(mutated_string.contains("jumped") && !(mutated_string.contains("lazy"))));
-        if unicode {
-            assert!(mutated_string.contains("𝖟") || mutated_string.contains("𝓵") || mutated_string.contains("𝓰"));
-        } 
-    }
-    
-    #[test_case("The quick brown fox jumped over the lazy dog." ; "without unicode")]
-    #[test_case("Тhe quick brown fox jumped over the lazy dog." ; "with unicode")]
-    fn can_lowercase_style(s: &str) {
-        let data = r#"
-        {
-            "handle": "yourHandle",
-            "acronym": "YH",
-            "quirks": [
-                {
-                    "style": "lowercase"
-                }
-            ]
-        }
-        "#;
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = format!("{}", s);
-    
-        let mutated_string = c.quirked(test_string.as_str());
-        
-        assert!(mutated_string == "YH: the quick brown fox jumped over the lazy dog." ||
-                mutated_string == "YH: тhe quick brown fox jumped over the lazy dog.");    
-    }
-    
-    #[test_case("The quick brown fox jumped over the lazy dog." ; "without unicode")]
-    #[test_case("ThЭ quick brown fox jumped over the lazy dog." ; "with unicode")]
-    fn can_uppercase_style(s: &str) {
-        let data = r#"
-        {
-            "handle": "yourHandle",
-            "acronym": "YH",
-            "quirks": [
-                {
-                    "style": "uppercase"
-                }
-            ]
-        }
-        "#;
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = format!("{}", s);
-    
-        let mutated_string = c.quirked(test_string.as_str());
-        
-        assert!(mutated_string == "YH: THE QUICK BROWN FOX JUMPED OVER THE LAZY DOG." ||
-                mutated_string == "YH: THЭ QUICK BROWN FOX JUMPED OVER THE LAZY DOG."); 
-    }
-
-    #[test_case("The quick brown fox jumped over the lazy dog." ; "without unicode")]
-    #[test_case("ThЭ quick brown fox jumped over the lazy dog." ; "with unicode")]
-    fn can_alternating_style(s: &str) {
-        let data = r#"
-        {
-            "handle": "yourHandle",
-            "acronym": "YH",
-            "quirks": [
-                {
-                    "style": "alternating"
-                }
-            ]
-        }
-        "#;
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = format!("{}", s);
-    
-        let mutated_string = c.quirked(test_string.as_str());
-        
-        assert!(mutated_string == "YH: ThE QuIcK BrOwN FoX JuMpEd oVeR ThE LaZy dOg." ||
-                mutated_string == "YH: ThЭ QuIcK BrOwN FoX JuMpEd oVeR ThE LaZy dOg."); 
-    }
-
-    #[test_case("The quick brown fox jumped over the lazy dog." ; "without unicode")]
-    #[test_case("ThЭ quick brown fox jumped over the lazy dog." ; "with unicode")]
-    fn can_camelcase_style(s: &str) {
-        let data = r#"
-        {
-            "handle": "yourHandle",
-            "acronym": "YH",
-            "quirks": [
-                {
-                    "style": "camelcase"
-                }
-            ]
-        }
-        "#;
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = format!("{}", s);
-    
-        let mutated_string = c.quirked(test_string.as_str());
-        
-        assert!(mutated_string == "YH: The Quick Brown Fox Jumped Over The Lazy Dog." ||
-                mutated_string == "YH: ThЭ Quick Brown Fox Jumped Over The Lazy Dog."); 
-    }
-
-    #[test_case("The quick brown fox jumped over the lazy dog." ; "without unicode")]
-    #[test_case("ThЭ quick brown fox jumped over the lazy dog." ; "with unicode")]
-    fn can_reverse_style(s: &str) {
-        let data = r#"
-        {
-            "handle": "yourHandle",
-            "acronym": "YH",
-            "quirks": [
-                {
-                    "style": "reverse"
-                }
-            ]
-        }
-        "#;
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = format!("{}", s);
-    
-        let mutated_string = c.quirked(test_string.as_str());
-        
-        assert!(mutated_string == "YH: .god yzal eht revo depmuj xof nworb kciuq ehT" ||
-                mutated_string == "YH: .god yzal eht revo depmuj xof nworb kciuq ЭhT"); 
-    }
-
-    #[test_case("The quick brown fox jumped over the lazy dog." ; "without unicode")]
-    #[test_case("ThЭ quick brown fox jumped over the lazy dog." ; "with unicode")]
-    fn can_inverted_style(s: &str) {
-        let data = r#"
-        {
-            "handle": "yourHandle",
-            "acronym": "YH",
-            "quirks": [
-                {
-                    "style": "inverted"
-                }
-            ]
-        }
-        "#;
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = format!("{}", s);
-    
-        let mutated_string = c.quirked(test_string.as_str());
-        
-        assert!(mutated_string == "YH: tHE QUICK BROWN FOX JUMPED OVER THE LAZY DOG." ||
-                mutated_string == "YH: tHэ QUICK BROWN FOX JUMPED OVER THE LAZY DOG."); 
-    }
-
-    #[test]
-    fn can_combine_quirks() {
-        let data = r#"
-        {
-            "handle": "yourHandle",
-            "acronym": "YH",
-            "quirks": [
-                {
-                    "style": "reverse"
-                },
-                {
-                    "style": "uppercase"
-                }
-            ]
-        }
-        "#;
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = "The quick brown fox jumped over the lazy dog.";
-    
-        let mutated_string = c.quirked(test_string);
-        
-        assert!(mutated_string == "YH: .GOD YZAL EHT REVO DEPMUJ XOF NWORB KCIUQ EHT"); 
-    }
-
-    #[test_case( "" ; "Trial one")]
-    #[test_case( "" ; "Trial two")]
-    #[test_case( "" ; "Trial three")]
-    #[test_case( "" ; "Trial four")]
-    #[test_case( "" ; "Trial five")]
-    fn quirks_are_deterministic(_s: &str) {
-        let data = r#"
-        {
-            "handle": "syntaxError",
-            "acronym": "`SE",
-            "quirks": [
-                {
-                    "style": "lowercase"
-                },
-                {
-                    "prefix": ">_ "
-                },
-                {
-                    "suffix": "`"
-                },
-                {
-                    "simple_replacements": [
-                        [
-                            "lorem", "[<censor>]"
-                        ],
-                        [
-                            "ipsum", "[<censor>]"
-                        ],
-                        [
-                            "dolar", "[<censor>]"
-                        ],
-                        [
-                            "somet", "[<mini-censor>]"
-                        ]
-                    ]
-                },
-                {
-                    "simple_replacements": [
-                        [
-                            "<mini-censor>", "▜▟"
-                        ],
-                        [
-                            "<censor>", "▛▜▟▙"
-                        ]
-                    ]
-                },
-                {
-                    "simple_replacements": [
-                        [
-                            ".", ""
-                        ],
-                        [
-                            ",", ""
-                        ],
-                        [
-                            "'", ""
-                        ],
-                        [
-                            "ing", "in"
-                        ],
-                        [
-                            "l", "1"
-                        ],
-                        [
-                            "i", "1"
-                        ],
-                        [
-                            "o", "0"
-                        ]
-                    ]
-                }
-            ]
-        }
-        "#;
-        let c = quirk::parse_safe(data.to_string());
-        let test_string = "The lorem dolar fox jumped ipsum the somet dog.";
-    
-        let mutated_string = c.quirked(test_string);
-        
-        assert!(mutated_string == "`SE: >_ the [▛▜▟▙] [▛▜▟▙] f0x jumped [▛▜▟▙] the [▜▟] d0g`");
+fn quirks_dir() -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("quirks")
+}
+
+#[cfg(test)]
+mod bot {
+    use discord::model::{ChannelId, MessageId, UserId};
+
+    use super::quirks_dir;
+    use crate::backend::{ChatError, ChatEvent};
+    use crate::bot::Bot;
+    use crate::mock::MockBackend;
+
+    const US: UserId = UserId(1);
+    const THEM: UserId = UserId(2);
+
+    #[test]
+    fn quirks_our_messages() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello there");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited(), vec![(ChannelId(100), MessageId(10), "AA: hello there".to_string())]);
+        assert!(backend.messages.deleted().is_empty());
     }
 
     #[test]
-    fn can_get_character_from_name() {
-        let c = Character::from_name("ARADIA");
-        if !c.is_some() {
-            panic!("Character quirk file could not be loaded.")
-        }
+    fn leaves_other_messages_alone() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "just talking");
+        backend.message(11, 100, THEM, "ARADIA: Hello there");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert!(backend.messages.edited().is_empty());
+    }
+
+    #[test]
+    fn runs_commands() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA! join");
+        let mut bot = Bot::new(quirks_dir());
+        bot.run(&mut backend);
+
+        assert_eq!(backend.messages.edited()[0].2, "```\n-- apocalypseArisen [AA] has joined the memo! --\n```");
+        assert!(bot.state.memos.lock().unwrap()[&ChannelId(100)].is_participant("apocalypseArisen"));
+    }
+
+    #[test]
+    fn keeps_memos_per_channel() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA! timeline -2:00");
+        backend.message(11, 100, US, "ARADIA: hi");
+        backend.message(12, 200, US, "ARADIA: hi");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited()[1].2, "PAA: hi");
+        assert_eq!(backend.messages.edited()[2].2, "AA: hi");
+    }
+
+    #[test]
+    fn mentions_use_last_played_character() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, THEM, "KARKAT: HEY");
+        backend.message(11, 100, US, "ARADIA! kick <@2>");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited()[0].2, "```\n-- apocalypseArisen [AA] has kicked carcinoGeneticist [CG] from the memo! --\n```");
+    }
+
+    #[test]
+    fn leaves_mentions_outside_known_commands() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, THEM, "KARKAT: HEY");
+        backend.message(11, 100, US, "wow! look <@2>\nARADIA! dance with <@2>");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert!(backend.messages.edited().is_empty());
+    }
+
+    #[test]
+    fn stops_when_closed_and_skips_other_errors() {
+        let mut backend = MockBackend::new(US);
+        backend.events.push_back(Err(ChatError::Other("hiccup".to_string())));
+        backend.events.push_back(Ok(ChatEvent::Other));
+        backend.events.push_back(Err(ChatError::Closed(Some(4004), "Authentication failed.".to_string())));
+        backend.message(10, 100, US, "ARADIA: never seen");
+
+        let closed = Bot::new(quirks_dir()).run(&mut backend);
+        assert_eq!(closed, ChatError::Closed(Some(4004), "Authentication failed.".to_string()));
+        assert!(backend.messages.edited().is_empty());
+    }
+}
+
+#[cfg(test)]
+use rustblood_core::testing::TempDir;
+
+/// A quirks folder holding a couple of characters and the given settings.
+#[cfg(test)]
+fn quirks_with_settings(name: &str, settings: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    for file in ["ARADIA.json", "KARKAT.json"] {
+        std::fs::copy(quirks_dir().join(file), dir.join(file)).unwrap();
+    }
+    std::fs::write(dir.join("_settings.json"), settings).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod delivery {
+    use discord::model::{ChannelId, MessageId, UserId};
+
+    use super::{quirks_dir, quirks_with_settings};
+    use crate::bot::Bot;
+    use crate::mock::MockBackend;
+
+    const US: UserId = UserId(1);
+
+    #[test]
+    fn can_resend() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        let dir = quirks_with_settings("resend", "{ \"delivery\": \"resend\" }");
+        Bot::new(dir.to_path_buf()).run(&mut backend);
+
+        assert_eq!(backend.messages.resent(), vec![(ChannelId(100), MessageId(10), "AA: hello".to_string())]);
+        assert_eq!(backend.messages.deleted(), vec![(ChannelId(100), MessageId(10))]);
+        assert!(backend.messages.edited().is_empty());
+    }
+
+    #[test]
+    fn can_post_as_character() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        let dir = quirks_with_settings("webhook", "{ \"delivery\": \"webhook\" }");
+        Bot::new(dir.to_path_buf()).run(&mut backend);
+
+        assert_eq!(backend.messages.sent_as(), vec![(ChannelId(100), "apocalypseArisen".to_string(), "AA: hello".to_string())]);
+        assert_eq!(backend.messages.deleted(), vec![(ChannelId(100), MessageId(10))]);
+    }
+
+    #[test]
+    fn webhook_without_character_edits() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "!list");
+        let dir = quirks_with_settings("webhook-list", "{ \"delivery\": \"webhook\" }");
+        Bot::new(dir.to_path_buf()).run(&mut backend);
+
+        assert!(backend.messages.sent_as().is_empty());
+        assert_eq!(backend.messages.edited().len(), 1);
+    }
+
+    #[test]
+    fn webhook_with_several_characters_edits() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello\nKARKAT: Hey");
+        let dir = quirks_with_settings("webhook-several", "{ \"delivery\": \"webhook\" }");
+        Bot::new(dir.to_path_buf()).run(&mut backend);
+
+        assert!(backend.messages.sent_as().is_empty());
+        assert_eq!(backend.messages.edited().len(), 1);
+    }
+
+    #[test]
+    fn failed_resend_edits_instead() {
+        let mut backend = MockBackend::new(US);
+        backend.messages.fail("resend_message");
+        backend.message(10, 100, US, "ARADIA: Hello");
+        let dir = quirks_with_settings("resend-fails", "{ \"delivery\": \"resend\" }");
+        Bot::new(dir.to_path_buf()).run(&mut backend);
+
+        assert!(backend.messages.deleted().is_empty());
+        assert_eq!(backend.messages.edited(), vec![(ChannelId(100), MessageId(10), "AA: hello".to_string())]);
+    }
+
+    #[test]
+    fn resent_messages_are_left_alone() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        // A character named after ARADIA's acronym would quirk the resent message again.
+        let dir = quirks_with_settings("resend-echo", "{ \"delivery\": \"resend\" }");
+        std::fs::copy(quirks_dir().join("KARKAT.json"), dir.to_path_buf().join("AA.json")).unwrap();
+        let mut bot = Bot::new(dir.to_path_buf());
+
+        let event = backend.events.pop_front().unwrap().unwrap();
+        bot.handle(&mut backend, event);
+        bot.flush();
+        bot.run(&mut backend);
+
+        assert_eq!(backend.messages.resent(), vec![(ChannelId(100), MessageId(10), "AA: hello".to_string())]);
+        assert!(backend.messages.edited().is_empty());
+    }
+
+    #[test]
+    fn resent_messages_can_be_undone() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.message(11, 100, US, "!undo");
+        let dir = quirks_with_settings("resend-undo", "{ \"delivery\": \"resend\" }");
+        Bot::new(dir.to_path_buf()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited(), vec![(ChannelId(100), MessageId(1000), "ARADIA: Hello".to_string())]);
     }
 
     #[test]
-    fn cant_get_character_from_invalid_name() {
-        let c = Character::from_name("MISSING");
-        if c.is_some() {
-            panic!("Missing character quirk file should not return a character")
+    fn character_posts_are_changed_through_the_webhook() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.message(11, 100, US, "!undo");
+        backend.react(1000, 100, US, "🗑️");
+        let dir = quirks_with_settings("webhook-undo", "{ \"delivery\": \"webhook\", \"reactions\": { \"🗑️\": \"delete\" } }");
+        Bot::new(dir.to_path_buf()).run(&mut backend);
+
+        assert!(backend.messages.edited().is_empty());
+        assert_eq!(backend.messages.webhook_edited(), vec![(ChannelId(100), MessageId(1000), "ARADIA: Hello".to_string())]);
+        assert_eq!(backend.messages.webhook_deleted(), vec![(ChannelId(100), MessageId(1000))]);
+    }
+}
+
+#[cfg(test)]
+mod account {
+    use discord::model::{ChannelId, ServerId, UserId};
+    use rustblood_core::{Presence, PresenceChange};
+
+    use super::{quirks_dir, quirks_with_settings};
+    use crate::bot::Bot;
+    use crate::mock::MockBackend;
+
+    const US: UserId = UserId(1);
+
+    #[test]
+    fn presence_is_left_alone_by_default() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA! idle");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert!(backend.presences.is_empty());
+    }
+
+    #[test]
+    fn can_sync_presence() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA! idle");
+        let dir = quirks_with_settings("presence", "{ \"sync_presence\": true }");
+        Bot::new(dir.to_path_buf()).run(&mut backend);
+
+        assert_eq!(backend.presences, vec![PresenceChange { presence: Presence::Idle, status: Some("communing with the dead".to_string()) }]);
+    }
+
+    #[test]
+    fn presence_changes_straight_away() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA! idle");
+        let dir = quirks_with_settings("presence-now", "{ \"sync_presence\": true }");
+        let mut bot = Bot::new(dir.to_path_buf());
+
+        let event = backend.events.pop_front().unwrap().unwrap();
+        bot.handle(&mut backend, event);
+        assert_eq!(backend.presences.len(), 1);
+        bot.flush();
+    }
+
+    #[test]
+    fn nickname_follows_character_and_is_restored() {
+        let mut backend = MockBackend::new(US);
+        backend.messages.add_channel(ChannelId(100), ServerId(7));
+        backend.messages.set_nickname_of(ServerId(7), US, "me");
+        backend.message(10, 100, US, "ARADIA: hi");
+        let dir = quirks_with_settings("nicknames", "{ \"nickname_servers\": [7], \"nickname_cooldown\": 0 }");
+        let mut bot = Bot::new(dir.to_path_buf());
+
+        let event = backend.events.pop_front().unwrap().unwrap();
+        bot.handle(&mut backend, event);
+        bot.flush();
+        assert_eq!(backend.messages.nickname_of(ServerId(7), US).unwrap(), "apocalypseArisen");
+
+        backend.message(11, 100, US, "ARADIA! offline");
+        bot.run(&mut backend);
+        assert_eq!(backend.messages.nickname_of(ServerId(7), US).unwrap(), "me");
+    }
+
+    #[test]
+    fn shutting_down_restores_nickname() {
+        let mut backend = MockBackend::new(US);
+        backend.messages.add_channel(ChannelId(100), ServerId(7));
+        backend.messages.set_nickname_of(ServerId(7), US, "me");
+        backend.message(10, 100, US, "ARADIA: hi");
+        backend.message(11, 100, US, "ARADIA: still here");
+        let dir = quirks_with_settings("shutdown", "{ \"nickname_servers\": [7] }");
+        let mut bot = Bot::new(dir.to_path_buf());
+
+        let event = backend.events.pop_front().unwrap().unwrap();
+        bot.handle(&mut backend, event);
+        bot.shutdown(&backend).run();
+        assert_eq!(backend.messages.edited().len(), 1);
+        assert_eq!(backend.messages.nickname_of(ServerId(7), US).unwrap(), "me");
+
+        let event = backend.events.pop_front().unwrap().unwrap();
+        bot.handle(&mut backend, event);
+        bot.flush();
+        assert_eq!(backend.messages.edited().len(), 1);
+    }
+
+    #[test]
+    fn nickname_is_left_alone_in_other_servers() {
+        let mut backend = MockBackend::new(US);
+        backend.messages.add_channel(ChannelId(100), ServerId(8));
+        backend.message(10, 100, US, "ARADIA: hi");
+        let dir = quirks_with_settings("other-server", "{ \"nickname_servers\": [7] }");
+        Bot::new(dir.to_path_buf()).run(&mut backend);
+
+        assert!(backend.messages.nicknames.lock().unwrap().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod reconnect {
+    use std::time::Duration;
+
+    use discord::model::UserId;
+
+    use super::quirks_dir;
+    use crate::backend::ChatError;
+    use crate::backoff::Backoff;
+    use crate::bot::Bot;
+    use crate::mock::MockBackend;
+
+    const US: UserId = UserId(1);
+
+    fn bot() -> Bot {
+        let mut bot = Bot::new(quirks_dir());
+        bot.backoff = Backoff::new(Duration::ZERO, Duration::ZERO);
+        bot
+    }
+
+    #[test]
+    fn reconnects_after_transient_close() {
+        let mut backend = MockBackend::new(US);
+        backend.events.push_back(Err(ChatError::Closed(Some(1006), "gone".to_string())));
+        backend.message(10, 100, US, "ARADIA: back again");
+        bot().run(&mut backend);
+
+        assert_eq!(backend.reconnects, 1);
+        assert_eq!(backend.messages.edited()[0].2, "AA: back again");
+    }
+
+    #[test]
+    fn keeps_trying_to_reconnect() {
+        let mut backend = MockBackend::new(US);
+        backend.reconnect_failures = 3;
+        backend.events.push_back(Err(ChatError::Closed(None, "gone".to_string())));
+        backend.message(10, 100, US, "ARADIA: back again");
+        let mut bot = bot();
+        bot.run(&mut backend);
+
+        assert_eq!(backend.reconnects, 1);
+        assert_eq!(backend.messages.edited().len(), 1);
+        assert_eq!(bot.backoff.attempts(), 0);
+    }
+
+    #[test]
+    fn gives_up_on_fatal_close() {
+        let mut backend = MockBackend::new(US);
+        backend.events.push_back(Err(ChatError::Closed(Some(4014), "Disallowed intent(s).".to_string())));
+        backend.message(10, 100, US, "ARADIA: never seen");
+        bot().run(&mut backend);
+
+        assert_eq!(backend.reconnects, 0);
+        assert!(backend.messages.edited().is_empty());
+    }
+
+    #[test]
+    fn gives_up_when_token_is_rejected_while_reconnecting() {
+        let mut backend = MockBackend::new(US);
+        backend.reconnect_failures = u32::MAX;
+        backend.reconnect_error = ChatError::Unauthorized;
+        backend.events.push_back(Err(ChatError::Closed(None, "gone".to_string())));
+        backend.message(10, 100, US, "ARADIA: never seen");
+
+        assert_eq!(bot().run(&mut backend), ChatError::Unauthorized);
+        assert_eq!(backend.reconnect_failures, u32::MAX - 1);
+        assert!(backend.messages.edited().is_empty());
+    }
+
+    #[test]
+    fn fatal_codes() {
+        assert!(ChatError::Unauthorized.is_fatal());
+        assert!(ChatError::Closed(Some(4004), String::new()).is_fatal());
+        assert!(!ChatError::Closed(Some(4000), String::new()).is_fatal());
+        assert!(!ChatError::Closed(None, String::new()).is_fatal());
+        assert!(!ChatError::Other(String::new()).is_fatal());
+    }
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(10));
+        let mut ceilings = Vec::new();
+        for _ in 0..6 {
+            ceilings.push(backoff.delay(1.0));
+            let delay = backoff.next_delay();
+            assert!(delay >= ceilings.last().unwrap().div_f64(2.0) && delay <= *ceilings.last().unwrap());
         }
+        let secs: Vec<u64> = ceilings.iter().map(|d| d.as_secs()).collect();
+        assert_eq!(secs, vec![1, 2, 4, 8, 10, 10]);
+
+        backoff.reset();
+        assert_eq!(backoff.delay(0.0), Duration::from_millis(500));
+    }
+}
+
+#[cfg(test)]
+mod edits {
+    use std::time::Duration;
+
+    use discord::model::{ChannelId, MessageId, UserId};
+
+    use super::quirks_dir;
+    use crate::backend::ChatError;
+    use crate::bot::Bot;
+    use crate::edits::EditQueue;
+    use crate::mock::{MockBackend, MockMessages};
+    use crate::rest::status_error;
+
+    const US: UserId = UserId(1);
+
+    fn queue() -> EditQueue {
+        let mut edits = EditQueue::default();
+        edits.retry_delay = Duration::ZERO;
+        edits
     }
 
     #[test]
-    fn can_quirk_multiline() {
-        let string = r#"ARADIA: Lorem ipsum dolar somet.
-NOTRADIA: Nothing to see here."#;
+    fn retries_failed_edits() {
+        let messages = MockMessages::default();
+        messages.edit_errors.lock().unwrap().extend([ChatError::Other("500".to_string()), ChatError::Other("502".to_string())]);
+        let mut edits = queue();
+        edits.push(ChannelId(100), MessageId(10), "AA: hi");
+        edits.flush(&messages);
+
+        assert_eq!(messages.edited(), vec![(ChannelId(100), MessageId(10), "AA: hi".to_string())]);
+        assert!(edits.is_empty());
+    }
 
-        let expected_string = r#"AA: lorem ipsum dolar somet
-NOTRADIA: Nothing to see here."#;
+    #[test]
+    fn http_statuses_are_handled_like_discord_errors() {
+        assert_eq!(status_error(404, None), Some(ChatError::NotFound));
+        assert_eq!(status_error(401, None), Some(ChatError::Unauthorized));
+        assert_eq!(status_error(429, Some("2.5")), Some(ChatError::RateLimited(Duration::from_millis(2500))));
+        assert_eq!(status_error(429, None), Some(ChatError::RateLimited(Duration::from_secs(1))));
+        assert_eq!(status_error(500, None), None);
+    }
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let messages = MockMessages::default();
+        messages.fail("edit_message");
+        let mut edits = queue();
+        edits.push(ChannelId(100), MessageId(10), "AA: hi");
+        edits.flush(&messages);
+
+        assert!(edits.is_empty());
     }
 
     #[test]
-    fn can_join_memo() {
-        let string = "ARADIA! join";
-        let expected_string = 
-r#"```
--- apocalypseArisen [AA] has joined the memo! --
-```"#;
+    fn drops_edits_to_deleted_messages() {
+        let messages = MockMessages::default();
+        messages.edit_errors.lock().unwrap().push_back(ChatError::NotFound);
+        let mut edits = queue();
+        edits.push(ChannelId(100), MessageId(10), "AA: gone");
+        edits.push(ChannelId(100), MessageId(11), "AA: still here");
+        edits.flush(&messages);
+
+        assert_eq!(messages.edited(), vec![(ChannelId(100), MessageId(11), "AA: still here".to_string())]);
+    }
+
+    #[test]
+    fn waits_out_short_rate_limits() {
+        let messages = MockMessages::default();
+        messages.edit_errors.lock().unwrap().push_back(ChatError::RateLimited(Duration::from_millis(10)));
+        let mut edits = queue();
+        edits.push(ChannelId(100), MessageId(10), "AA: one");
+        edits.push(ChannelId(100), MessageId(11), "AA: two");
+        edits.flush(&messages);
+
+        let order: Vec<MessageId> = messages.edited().iter().map(|e| e.1).collect();
+        assert_eq!(order, vec![MessageId(10), MessageId(11)]);
+    }
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+    #[test]
+    fn holds_edits_back_during_long_rate_limits() {
+        let messages = MockMessages::default();
+        messages.edit_errors.lock().unwrap().push_back(ChatError::RateLimited(Duration::from_secs(60)));
+        let mut edits = queue();
+        edits.push(ChannelId(100), MessageId(10), "AA: one");
+        edits.push(ChannelId(100), MessageId(11), "AA: two");
+        edits.flush(&messages);
+
+        assert!(messages.edited().is_empty());
+        assert_eq!(edits.len(), 2);
     }
 
+    #[test]
+    fn newer_edit_replaces_waiting_one() {
+        let messages = MockMessages::default();
+        let mut edits = queue();
+        edits.push(ChannelId(100), MessageId(10), "AA: first");
+        edits.push(ChannelId(100), MessageId(10), "AA: second");
+        edits.flush(&messages);
+
+        assert_eq!(messages.edited(), vec![(ChannelId(100), MessageId(10), "AA: second".to_string())]);
+    }
 
     #[test]
-    fn can_leave_memo() {
-        let string = "ARADIA! leave";
-        let expected_string = 
-r#"```
--- apocalypseArisen [AA] has left the memo! --
-```"#;
+    fn failed_edits_dont_stop_the_bot() {
+        let mut backend = MockBackend::new(US);
+        backend.messages.fail("edit_message");
+        backend.message(10, 100, US, "ARADIA: one");
+        backend.message(11, 100, US, "ARADIA! join");
+        let mut bot = Bot::new(quirks_dir());
+        bot.edits = queue();
+        bot.run(&mut backend);
+
+        assert!(bot.state.memos.lock().unwrap()[&ChannelId(100)].is_participant("apocalypseArisen"));
+    }
+}
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+#[cfg(test)]
+mod workers {
+    use std::path::Path;
+    use std::sync::{Arc, Condvar, Mutex};
+    use std::time::Duration;
+
+    use discord::model::{ChannelId, MessageId, ServerId, UserId};
+    use rustblood_core::Character;
+
+    use super::quirks_dir;
+    use crate::backend::{ChatEvent, ChatMessage, ChatReaction, ChatResult, MessageApi};
+    use crate::bot::Bot;
+    use crate::edits::EditQueue;
+    use crate::handler::Handler;
+    use crate::mock::{MockBackend, MockMessages};
+    use crate::workers::Workers;
+
+    const US: UserId = UserId(1);
+
+    fn handler(api: Arc<dyn MessageApi>) -> Arc<Handler> {
+        Arc::new(Handler { dir: quirks_dir(), user: US, api, state: Arc::default() })
+    }
+
+    fn message(channel: u64, id: u64, content: &str) -> ChatEvent {
+        ChatEvent::MessageCreate(ChatMessage {
+            id: MessageId(id),
+            channel_id: ChannelId(channel),
+            author: US,
+            content: content.to_string(),
+            mentions: Vec::new(),
+            reference: None,
+        })
     }
 
     #[test]
-    fn can_go_online() {
-        let string = "ARADIA! online";
-        let expected_string = 
-r#"```
--- apocalypseArisen [AA] is now online! --
-```"#;
+    fn keeps_each_channel_in_order() {
+        let messages = Arc::new(MockMessages::new(US));
+        let workers = Workers::new(3, handler(messages.clone()), &EditQueue::default());
+        for i in 0..20 {
+            workers.submit(message(100 + i % 2, i, &format!("ARADIA: {}", i)));
+        }
+        workers.join();
+
+        for channel in [100, 101] {
+            let order: Vec<u64> = messages.edited().iter().filter(|e| e.0 == ChannelId(channel)).map(|e| e.1.0).collect();
+            let mut sorted = order.clone();
+            sorted.sort();
+            assert_eq!(order.len(), 10);
+            assert_eq!(order, sorted);
+        }
+    }
+
+    #[test]
+    fn workers_carry_on_after_a_panic() {
+        let messages = Arc::new(MockMessages::new(US));
+        messages.panicking.lock().unwrap().push("server_of");
+        let workers = Workers::new(1, handler(messages.clone()), &EditQueue::default());
+        workers.submit(message(100, 10, "ARADIA! kick <@2>"));
+        workers.submit(message(100, 11, "ARADIA: hi"));
+        workers.join();
+
+        assert_eq!(messages.edited(), vec![(ChannelId(100), MessageId(11), "AA: hi".to_string())]);
+    }
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+    /// Holds up edits in channel 1 until one has been made in channel 2.
+    #[derive(Default)]
+    struct Blocking {
+        done: Mutex<Vec<u64>>,
+        changed: Condvar,
+    }
+
+    impl MessageApi for Blocking {
+        fn delete_message(&self, _: ChannelId, _: MessageId) -> ChatResult<()> { Ok(()) }
+        fn resend_message(&self, _: ChannelId, message: MessageId, _: &str) -> ChatResult<MessageId> { Ok(message) }
+        fn send_as(&self, _: ChannelId, message: MessageId, _: &Character, _: &Path, _: &str) -> ChatResult<MessageId> { Ok(message) }
+        fn edit_webhook_message(&self, _: ChannelId, _: MessageId, _: &str) -> ChatResult<()> { Ok(()) }
+        fn delete_webhook_message(&self, _: ChannelId, _: MessageId) -> ChatResult<()> { Ok(()) }
+        fn remove_reaction(&self, _: &ChatReaction) -> ChatResult<()> { Ok(()) }
+        fn server_of(&self, _: ChannelId) -> Option<ServerId> { None }
+        fn nickname(&self, _: ServerId, _: UserId) -> ChatResult<Option<String>> { Ok(None) }
+        fn set_nickname(&self, _: ServerId, _: &str) -> ChatResult<()> { Ok(()) }
+
+        fn edit_message(&self, channel: ChannelId, _: MessageId, _: &str) -> ChatResult<()> {
+            let mut done = self.done.lock().unwrap();
+            if channel.0 == 1 {
+                done = self.changed.wait_timeout_while(done, Duration::from_secs(5), |d| !d.contains(&2)).unwrap().0;
+            }
+            done.push(channel.0);
+            self.changed.notify_all();
+            Ok(())
+        }
     }
 
     #[test]
-    fn can_go_offline() {
-        let string = "ARADIA! offline";
-        let expected_string = 
-r#"```
--- apocalypseArisen [AA] is now offline! --
-```"#;
+    fn channels_dont_wait_for_each_other() {
+        let api = Arc::new(Blocking::default());
+        let workers = Workers::new(2, handler(api.clone()), &EditQueue::default());
+        workers.submit(message(1, 10, "ARADIA: slow"));
+        workers.submit(message(2, 11, "ARADIA: fast"));
+        workers.join();
+
+        assert_eq!(*api.done.lock().unwrap(), vec![2, 1]);
+    }
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+    #[test]
+    fn bot_finishes_sending_before_run_returns() {
+        let mut backend = MockBackend::new(US);
+        for i in 0..10 {
+            backend.message(i, 100 + i % 3, US, "ARADIA: hi");
+        }
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited().len(), 10);
+    }
+}
+
+#[cfg(test)]
+mod updates {
+    use discord::model::{ChannelId, MessageId, UserId};
+
+    use super::{quirks_dir, quirks_with_settings};
+    use crate::bot::Bot;
+    use crate::mock::MockBackend;
+
+    const US: UserId = UserId(1);
+    const THEM: UserId = UserId(2);
+
+    #[test]
+    fn requirks_our_edits() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.update(10, 100, US, "AA: hello");
+        backend.update(10, 100, US, "ARADIA: Goodbye");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited(), vec![
+            (ChannelId(100), MessageId(10), "AA: hello".to_string()),
+            (ChannelId(100), MessageId(10), "AA: goodbye".to_string()),
+        ]);
     }
 
     #[test]
-    fn can_go_idle() {
-        let string = "ARADIA! idle";
-        let expected_string = 
-r#"```
--- apocalypseArisen [AA] is now idle! --
-```"#;
+    fn ignores_our_own_edits() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA! join");
+        backend.update(10, 100, US, "```\n-- apocalypseArisen [AA] has joined the memo! --\n```");
+        Bot::new(quirks_dir()).run(&mut backend);
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+        assert_eq!(backend.messages.edited().len(), 1);
     }
 
     #[test]
-    fn can_go_unidle() {
-        let string = "ARADIA! unidle";
-        let expected_string = 
-r#"```
--- apocalypseArisen [AA] is no longer idle! --
-```"#;
+    fn ignores_other_peoples_edits() {
+        let mut backend = MockBackend::new(US);
+        backend.update(11, 100, THEM, "ARADIA: Hello");
+        Bot::new(quirks_dir()).run(&mut backend);
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+        assert!(backend.messages.edited().is_empty());
     }
 
     #[test]
-    fn can_block() {
-        let string = "ARADIA! block ectoBiologist";
-        let expected_string = 
-r#"```
--- apocalypseArisen [AA] has blocked ectoBiologist [EB]! --
-```
-"#;
+    fn edits_in_place_whatever_the_delivery() {
+        let mut backend = MockBackend::new(US);
+        backend.update(10, 100, US, "ARADIA: Hello");
+        let dir = quirks_with_settings("resend-update", "{ \"delivery\": \"resend\" }");
+        Bot::new(dir.to_path_buf()).run(&mut backend);
+
+        assert!(backend.messages.resent().is_empty());
+        assert_eq!(backend.messages.edited(), vec![(ChannelId(100), MessageId(10), "AA: hello".to_string())]);
+    }
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+    #[test]
+    fn escaped_lines_stay_escaped_after_a_restart() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello\n\\KARKAT: yo");
+        Bot::new(quirks_dir()).run(&mut backend);
+        let quirked = backend.messages.edited()[0].2.clone();
+        assert_eq!(quirked, "AA: hello\n\u{200B}KARKAT: yo");
+
+        // A new bot doesn't remember the edit, so it sees the message afresh.
+        let mut backend = MockBackend::new(US);
+        backend.update(10, 100, US, &quirked);
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert!(backend.messages.edited().is_empty());
     }
+}
+
+#[cfg(test)]
+mod undo {
+    use discord::model::{ChannelId, MessageId, UserId};
+
+    use super::quirks_dir;
+    use crate::bot::Bot;
+    use crate::mock::MockBackend;
+
+    const US: UserId = UserId(1);
 
     #[test]
-    fn can_unblock() {
-        let string = "ARADIA! unblock ectoBiologist";
-        let expected_string = 
-r#"```
--- apocalypseArisen [AA] has unblocked ectoBiologist [EB]! --
-```
-"#;
+    fn restores_the_last_message() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.message(11, 100, US, "ARADIA: Goodbye");
+        backend.message(12, 100, US, "!undo");
+        backend.update(11, 100, US, "ARADIA: Goodbye");
+        backend.message(13, 100, US, "!undo");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        let edited = backend.messages.edited();
+        assert_eq!(edited.len(), 4);
+        assert_eq!(edited[2], (ChannelId(100), MessageId(11), "ARADIA: Goodbye".to_string()));
+        assert_eq!(edited[3], (ChannelId(100), MessageId(10), "ARADIA: Hello".to_string()));
+        assert_eq!(backend.messages.deleted(), vec![(ChannelId(100), MessageId(12)), (ChannelId(100), MessageId(13))]);
+    }
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+    #[test]
+    fn restores_a_message_by_id() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.message(11, 100, US, "ARADIA: Goodbye");
+        backend.message(12, 100, US, "!undo 10");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited()[2], (ChannelId(100), MessageId(10), "ARADIA: Hello".to_string()));
     }
 
     #[test]
-    fn can_upload() {
-        let string = "ARADIA! upload test.png";
-        let expected_string = 
-r#"```
--- apocalypseArisen [AA] has uploaded "test.png" --
-```
-"#;
+    fn only_looks_in_the_same_channel() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.message(11, 200, US, "!undo");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited().len(), 1);
+        assert!(backend.messages.deleted().is_empty());
+    }
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+    #[test]
+    fn restores_the_message_replied_to() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.message(11, 100, US, "ARADIA: Goodbye");
+        backend.reply(12, 100, US, "!undo", 10);
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited()[2], (ChannelId(100), MessageId(10), "ARADIA: Hello".to_string()));
+        assert_eq!(backend.messages.deleted(), vec![(ChannelId(100), MessageId(12))]);
     }
 
     #[test]
-    fn can_kick() {
-        let string = "ARADIA! kick ectoBiologist";
-        let expected_string = 
-r#"```
--- apocalypseArisen [AA] has kicked ectoBiologist [EB] from the memo! --
-```
-"#;
+    fn leaves_replies_to_unquirked_messages() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.message(11, 100, US, "just typing");
+        backend.reply(12, 100, US, "!undo", 11);
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited().len(), 1);
+        assert!(backend.messages.deleted().is_empty());
+    }
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+    #[test]
+    fn leaves_ids_from_other_channels() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.message(11, 200, US, "!undo 10");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited().len(), 1);
+        assert!(backend.messages.deleted().is_empty());
     }
+}
+
+#[cfg(test)]
+mod reroll {
+    use discord::model::{ChannelId, MessageId, UserId};
+
+    use super::quirks_dir;
+    use crate::bot::Bot;
+    use crate::mock::MockBackend;
+
+    const US: UserId = UserId(1);
 
     #[test]
-    fn can_ban() {
-        let string = "ARADIA! ban ectoBiologist";
-        let expected_string = 
-r#"```
--- apocalypseArisen [AA] has banned ectoBiologist [EB] from the memo! --
-```
-"#;
+    fn quirks_the_last_message_again() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "TEST: <|RBULB|>");
+        backend.message(11, 100, US, "TEST! reroll");
+        let mut bot = Bot::new(quirks_dir());
+        bot.run(&mut backend);
+
+        let edited = backend.messages.edited();
+        assert_eq!(edited.len(), 2);
+        assert_eq!(edited[1].1, MessageId(10));
+        assert!(edited[1].2.starts_with("TH: https://"));
+        assert_ne!(edited[0].2, edited[1].2);
+        assert_eq!(bot.state.history.lock().unwrap().get(MessageId(10)).unwrap().quirked, edited[1].2);
+        assert_eq!(backend.messages.deleted(), vec![(ChannelId(100), MessageId(11))]);
+    }
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+    #[test]
+    fn needs_a_loaded_character() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.message(11, 100, US, "Wow! reroll the dice");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited().len(), 1);
+        assert!(backend.messages.deleted().is_empty());
     }
 
     #[test]
-    fn can_unban() {
-        let string = "ARADIA! unban ectoBiologist";
-        let expected_string = 
-r#"```
--- apocalypseArisen [AA] has unbanned ectoBiologist [EB] from the memo! --
-```
-"#;
+    fn is_left_alone_without_a_message_to_reroll() {
+        let mut backend = MockBackend::new(US);
+        backend.message(11, 100, US, "ARADIA! reroll");
+        Bot::new(quirks_dir()).run(&mut backend);
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+        assert!(backend.messages.deleted().is_empty());
     }
 
     #[test]
-    fn can_troll() {
-        let string = "ARADIA! troll ectoBiologist";
-        let expected_string = 
-r#"```
--- apocalypseArisen [AA] has begun trolling ectoBiologist [EB]! --
-```
-"#;
+    fn only_rerolls_messages_the_bot_quirked() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "TEST: <|RBULB|>");
+        backend.message(11, 100, US, "just typing");
+        backend.reply(12, 100, US, "ARADIA! reroll", 11);
+        backend.message(13, 200, US, "ARADIA! reroll 10");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited().len(), 1);
+        assert!(backend.messages.deleted().is_empty());
+    }
+
+    #[test]
+    fn leaves_unchanged_messages_alone() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.message(11, 100, US, "ARADIA! reroll");
+        Bot::new(quirks_dir()).run(&mut backend);
+
+        assert_eq!(backend.messages.edited().len(), 1);
+    }
 
-        let cs = Characters::from_string(string);
-        let quirked_text = cs.quirked();
-        assert_eq!(quirked_text, expected_string);
+    #[test]
+    fn doesnt_run_commands_twice() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA! join\nTEST: <|RBULB|>");
+        backend.message(11, 100, US, "ARADIA! reroll 10");
+        let mut bot = Bot::new(quirks_dir());
+        bot.run(&mut backend);
+
+        let edited = backend.messages.edited();
+        assert_eq!(edited.len(), 2);
+        assert!(edited[1].2.starts_with("```\n-- apocalypseArisen [AA] has joined the memo! --\n```"));
+        assert!(bot.state.memos.lock().unwrap()[&ChannelId(100)].is_participant("apocalypseArisen"));
     }
 }
 
+#[cfg(test)]
+mod reactions {
+    use discord::model::{ChannelId, MessageId, UserId};
+
+    use super::quirks_with_settings;
+    use crate::bot::Bot;
+    use crate::mock::MockBackend;
+
+    const US: UserId = UserId(1);
+    const THEM: UserId = UserId(2);
+    const SETTINGS: &str = r#"{ "reactions": { "↩️": "undo", "🎲": "reroll", "🦀": { "quirk": "KARKAT" }, "🗑️": "delete" } }"#;
+
+    fn run(name: &str, backend: &mut MockBackend) {
+        let dir = quirks_with_settings(name, SETTINGS);
+        Bot::new(dir.to_path_buf()).run(backend);
+    }
+
+    #[test]
+    fn can_undo() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.react(10, 100, US, "↩️");
+        run("react-undo", &mut backend);
+
+        assert_eq!(backend.messages.edited()[1], (ChannelId(100), MessageId(10), "ARADIA: Hello".to_string()));
+        assert_eq!(backend.messages.unreacted(), vec![(MessageId(10), "↩️".to_string())]);
+    }
+
+    #[test]
+    fn can_quirk_as_another_character() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.react(10, 100, US, "🦀");
+        backend.react(10, 100, US, "↩️");
+        run("react-quirk", &mut backend);
+
+        let edited = backend.messages.edited();
+        assert_eq!(edited[1], (ChannelId(100), MessageId(10), "CG: HELLO".to_string()));
+        assert_eq!(edited[2].2, "ARADIA: Hello");
+    }
+
+    #[test]
+    fn can_delete() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.react(10, 100, US, "🗑️");
+        run("react-delete", &mut backend);
+
+        assert_eq!(backend.messages.deleted(), vec![(ChannelId(100), MessageId(10))]);
+        assert!(backend.messages.unreacted().is_empty());
+    }
+
+    #[test]
+    fn ignores_other_reactions() {
+        let mut backend = MockBackend::new(US);
+        backend.message(10, 100, US, "ARADIA: Hello");
+        backend.message(11, 100, US, "not quirked");
+        backend.react(10, 100, THEM, "↩️");
+        backend.react(10, 100, US, "👍");
+        backend.react(11, 100, US, "🗑️");
+        run("react-ignore", &mut backend);
+
+        assert_eq!(backend.messages.edited().len(), 1);
+        assert!(backend.messages.deleted().is_empty());
+        assert!(backend.messages.unreacted().is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use discord::model::{ChannelId, MessageId};
+use rustblood_core::Character;
+use serde_json::json;
+
+use crate::backend::{ChatError, ChatResult};
+use crate::resend::message_id;
+use crate::rest::Rest;
+
+/// Name of the webhooks we create, so they can be found again after a restart.
+const WEBHOOK_NAME: &str = "rustblood";
+
+#[derive(Clone)]
+struct Webhook {
+	id: String,
+	token: String,
+	/// The avatar file last uploaded as this webhook's own avatar.
+	avatar_file: Option<String>,
+}
+
+/// Posts messages under a character's handle and avatar through a webhook in each
+/// channel, creating the webhook the first time a channel needs one. The cache of
+/// webhooks is only locked to look one up or change it, never during a request.
+pub struct Webhooks {
+	rest: Rest,
+	hooks: Mutex<HashMap<ChannelId, Webhook>>,
+}
+
+impl Webhooks {
+	pub fn new(rest: Rest) -> Webhooks {
+		Webhooks {
+			rest,
+			hooks: Mutex::default(),
+		}
+	}
+
+	/// Posts `content` to the channel as the character, along with `files` given as
+	/// file names and contents. `dir` is the quirks folder, which avatar files are
+	/// relative to. Returns the new message's ID.
+	pub fn send(&self, channel: ChannelId, c: &Character, dir: &Path, content: &str, files: Vec<(String, Vec<u8>)>) -> ChatResult<MessageId> {
+		let hook = self.hook(channel)?;
+
+		// Webhook messages can only take an avatar by URL. A file is uploaded as the
+		// webhook's own avatar instead, which it keeps until another file is needed.
+		let mut body = json!({ "content": content, "username": c.handle });
+		match c.avatar.as_deref() {
+			Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+				body["avatar_url"] = json!(url);
+			},
+			Some(file) => self.set_avatar_file(channel, &hook, dir, file)?,
+			None => {},
+		}
+
+		// Waiting makes Discord answer with the message, which has the ID we need.
+		let path = format!("/webhooks/{}/{}?wait=true", hook.id, hook.token);
+		let sent = if files.is_empty() {
+			self.rest.post(&path, &body)
+		} else {
+			self.rest.post_with_files(&path, &body, files)
+		};
+		match sent {
+			Ok(message) => message_id(&message),
+			Err(err) => {
+				// The webhook may have been deleted, so look it up again next time.
+				self.hooks().remove(&channel);
+				Err(err)
+			},
+		}
+	}
+
+	/// Changes the text of a message the channel's webhook posted.
+	pub fn edit(&self, channel: ChannelId, message: MessageId, content: &str) -> ChatResult<()> {
+		let path = self.message_path(channel, message)?;
+		self.rest.patch(&path, &json!({ "content": content })).map(|_| ())
+	}
+
+	/// Deletes a message the channel's webhook posted.
+	pub fn delete(&self, channel: ChannelId, message: MessageId) -> ChatResult<()> {
+		let path = self.message_path(channel, message)?;
+		self.rest.delete(&path).map(|_| ())
+	}
+
+	fn message_path(&self, channel: ChannelId, message: MessageId) -> ChatResult<String> {
+		let hook = self.hook(channel)?;
+		Ok(format!("/webhooks/{}/{}/messages/{}", hook.id, hook.token, message.0))
+	}
+
+	/// The channel's webhook, found or created if it isn't known yet.
+	fn hook(&self, channel: ChannelId) -> ChatResult<Webhook> {
+		if let Some(hook) = self.hooks().get(&channel) {
+			return Ok(hook.clone());
+		}
+		let hook = self.find_or_create(channel)?;
+		Ok(self.hooks().entry(channel).or_insert(hook).clone())
+	}
+
+	fn find_or_create(&self, channel: ChannelId) -> ChatResult<Webhook> {
+		let existing = self.rest.get(&format!("/channels/{}/webhooks", channel.0))?;
+		let found = existing.as_array()
+			.and_then(|hooks| hooks.iter().find(|h| h["name"] == WEBHOOK_NAME && h["token"].is_string()))
+			.cloned();
+		let hook = match found {
+			Some(hook) => hook,
+			None => self.rest.post(&format!("/channels/{}/webhooks", channel.0), &json!({ "name": WEBHOOK_NAME }))?,
+		};
+
+		match (hook["id"].as_str(), hook["token"].as_str()) {
+			(Some(id), Some(token)) => Ok(Webhook { id: id.to_string(), token: token.to_string(), avatar_file: None }),
+			_ => Err(ChatError::Other("Discord returned a webhook without a token".to_string())),
+		}
+	}
+
+	fn set_avatar_file(&self, channel: ChannelId, hook: &Webhook, dir: &Path, file: &str) -> ChatResult<()> {
+		if hook.avatar_file.as_deref() == Some(file) {
+			return Ok(());
+		}
+
+		let path = dir.join(file);
+		let data = fs::read(&path).map_err(|e| ChatError::Other(format!("couldn't read avatar {}: {}", path.display(), e)))?;
+		let mime = match path.extension().and_then(|e| e.to_str()) {
+			Some("jpg") | Some("jpeg") => "image/jpeg",
+			Some("gif") => "image/gif",
+			_ => "image/png",
+		};
+		let avatar = format!("data:{};base64,{}", mime, base64::encode(data));
+		self.rest.patch(&format!("/webhooks/{}", hook.id), &json!({ "avatar": avatar }))?;
+
+		if let Some(hook) = self.hooks().get_mut(&channel) {
+			hook.avatar_file = Some(file.to_string());
+		}
+		Ok(())
+	}
+
+	fn hooks(&self) -> MutexGuard<'_, HashMap<ChannelId, Webhook>> {
+		self.hooks.lock().unwrap_or_else(|e| e.into_inner())
+	}
+}
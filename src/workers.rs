@@ -0,0 +1,90 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+use crate::backend::ChatEvent;
+use crate::edits::EditQueue;
+use crate::handler::Handler;
+
+/// Most events a worker holds before new ones have to wait for room.
+const QUEUE_SIZE: usize = 64;
+
+/// A fixed number of threads which handle events, so that quirking and sending in
+/// one channel doesn't hold up the others. Every event for a channel goes to the
+/// same worker, which keeps them in order.
+///
+/// These are plain threads rather than tasks on an async runtime: discord-rs and
+/// the HTTP client only make blocking calls, which would tie up a runtime's
+/// threads just the same.
+pub struct Workers {
+	queues: Vec<SyncSender<ChatEvent>>,
+	handles: Vec<JoinHandle<()>>,
+}
+
+impl Workers {
+	/// Starts `size` workers, each with its own copy of `edits` for retrying edits.
+	pub fn new(size: usize, handler: Arc<Handler>, edits: &EditQueue) -> Workers {
+		let mut queues = Vec::new();
+		let mut handles = Vec::new();
+		for _ in 0..size.max(1) {
+			let (sender, events) = mpsc::sync_channel(QUEUE_SIZE);
+			let (handler, edits) = (handler.clone(), edits.clone());
+			queues.push(sender);
+			handles.push(thread::spawn(move || work(events, handler, edits)));
+		}
+		Workers { queues, handles }
+	}
+
+	/// Hands an event to the worker for its channel, waiting if that worker is full.
+	/// Events outside a channel are dropped, since there's nothing to do with them.
+	pub fn submit(&self, event: ChatEvent) {
+		let channel = match event.channel() {
+			Some(channel) => channel,
+			None => return,
+		};
+		let queue = &self.queues[channel.0 as usize % self.queues.len()];
+		if queue.send(event).is_err() {
+			println!("A worker stopped, dropping an event");
+		}
+	}
+
+	/// Waits for every event and retry to finish, then stops the workers.
+	pub fn join(self) {
+		drop(self.queues);
+		for handle in self.handles {
+			let _ = handle.join();
+		}
+	}
+}
+
+fn work(events: Receiver<ChatEvent>, handler: Arc<Handler>, mut edits: EditQueue) {
+	loop {
+		// Wake up for a new event, or when the next edit waiting to be retried is ready.
+		let event = match edits.next_ready() {
+			None => match events.recv() {
+				Ok(event) => Some(event),
+				Err(_) => break,
+			},
+			Some(ready_at) => match events.recv_timeout(ready_at.saturating_duration_since(Instant::now())) {
+				Ok(event) => Some(event),
+				Err(RecvTimeoutError::Timeout) => None,
+				Err(RecvTimeoutError::Disconnected) => break,
+			},
+		};
+		if let Some(event) = event {
+			// A broken quirk file shouldn't stop the channels this worker looks after.
+			let handled = panic::catch_unwind(AssertUnwindSafe(|| handler.handle(event, &mut edits)));
+			if handled.is_err() {
+				println!("Couldn't handle an event, skipping it");
+			}
+		}
+		edits.flush(&*handler.api);
+	}
+
+	while let Some(ready_at) = edits.next_ready() {
+		thread::sleep(ready_at.saturating_duration_since(Instant::now()));
+		edits.flush(&*handler.api);
+	}
+}